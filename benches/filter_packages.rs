@@ -0,0 +1,39 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pmux::core::package_managers::Package;
+use pmux::ui::App;
+
+// Roughly the size of a full Arch sync database plus the AUR results mixed in
+const PACKAGE_COUNT: usize = 100_000;
+
+fn synthetic_packages() -> Vec<Package> {
+    (0..PACKAGE_COUNT)
+        .map(|i| Package {
+            name: format!("package-{}-{}", ["lib", "python", "rust", "font"][i % 4], i),
+            version: Some(format!("{}.{}.{}-1", i % 7, i % 13, i % 31)),
+            description: Some(format!("Synthetic package number {} used to benchmark search filtering", i)),
+            installed: i % 10 == 0,
+            source: ["pacman", "aur", "apt"][i % 3].to_string(),
+            provides: if i % 50 == 0 { vec![format!("virtual-{}", i)] } else { Vec::new() },
+            repo: Some(["core", "extra", "multilib"][i % 3].to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn filter_packages(c: &mut Criterion) {
+    let mut app = App::default();
+    app.set_packages(synthetic_packages());
+
+    for query in ["", "python", "Number 4242", "repo:extra font", "no-such-package"] {
+        app.search_input = query.to_string();
+        c.bench_function(&format!("filter_packages {:?}", query), |b| {
+            b.iter(|| {
+                app.filter_packages();
+                black_box(app.filtered_indices.len())
+            })
+        });
+    }
+}
+
+criterion_group!(benches, filter_packages);
+criterion_main!(benches);
@@ -37,8 +37,10 @@ impl ArchApi {
                 name: arch_pkg.pkgname,
                 version: Some(arch_pkg.pkgver),
                 description: arch_pkg.pkgdesc,
-                installed: false, // Will be determined later
                 source: "pacman".to_string(),
+                url: arch_pkg.url,
+                repo: Some(arch_pkg.repo),
+                ..Default::default()
             })
             .collect();
         
@@ -59,8 +61,10 @@ impl ArchApi {
             name: arch_pkg.pkgname,
             version: Some(arch_pkg.pkgver),
             description: arch_pkg.pkgdesc,
-            installed: false,
             source: "pacman".to_string(),
+            url: arch_pkg.url,
+            repo: Some(arch_pkg.repo),
+            ..Default::default()
         }))
     }
 }
\ No newline at end of file
@@ -1,14 +1,45 @@
 use serde::Deserialize;
 use crate::core::package_managers::Package;
 use std::error::Error;
+use std::sync::RwLock;
+
+pub const DEFAULT_AUR_RPC_URL: &str = "https://aur.archlinux.org/rpc/";
+
+// From `[network] aur_rpc_url`; set before any AUR request is made and again when the
+// config is reloaded
+static RPC_URL: RwLock<Option<String>> = RwLock::new(None);
+
+pub fn set_rpc_url(url: String) {
+    if let Ok(mut rpc_url) = RPC_URL.write() {
+        *rpc_url = Some(url);
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct AurResponse {
     pub resultcount: u32,
+    #[serde(default)]
     pub results: Vec<AurPackage>,
     #[serde(rename = "type")]
     pub response_type: String,
     pub version: u32,
+    pub error: Option<String>, // Set when response_type is "error", e.g. "Too many package results."
+}
+
+impl AurResponse {
+    // An "error" response comes back with an empty results list, which would otherwise
+    // look like nothing matched
+    pub fn into_results(self) -> Result<Vec<AurPackage>, Box<dyn Error + Send + Sync>> {
+        if self.response_type != "error" {
+            return Ok(self.results);
+        }
+        let error = self.error.unwrap_or_else(|| "unknown error".to_string());
+        if error.starts_with("Too many package results") {
+            Err("query too broad".into())
+        } else {
+            Err(error.into())
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,20 +75,27 @@ pub struct AurPackage {
 }
 
 pub struct AurClient {
-    base_url: String,
     client: reqwest::Client,
 }
 
 impl AurClient {
     pub fn new() -> Self {
         Self {
-            base_url: "https://aur.archlinux.org/rpc/".to_string(),
             client: reqwest::Client::new(),
         }
     }
 
+    // Read per request, since clients can be created before the config is loaded
+    fn base_url(&self) -> String {
+        RPC_URL
+            .read()
+            .ok()
+            .and_then(|rpc_url| rpc_url.clone())
+            .unwrap_or_else(|| DEFAULT_AUR_RPC_URL.to_string())
+    }
+
     pub async fn search(&self, query: &str) -> Result<Vec<Package>, Box<dyn Error + Send + Sync>> {
-        let url = format!("{}?v=5&type=search&arg={}", self.base_url, urlencoding::encode(query));
+        let url = format!("{}?v=5&type=search&arg={}", self.base_url(), urlencoding::encode(query));
         
         let response = self.client
             .get(&url)
@@ -67,14 +105,16 @@ impl AurClient {
 
         let aur_response: AurResponse = response.json().await?;
         
-        let packages = aur_response.results
+        let packages = aur_response.into_results()?
             .into_iter()
             .map(|aur_pkg| Package {
                 name: aur_pkg.name,
                 version: Some(aur_pkg.version),
                 description: aur_pkg.description,
-                installed: false, // We'll check this separately
                 source: "aur".to_string(),
+                url: aur_pkg.url,
+                out_of_date: aur_pkg.out_of_date,
+                ..Default::default()
             })
             .collect();
 
@@ -87,7 +127,7 @@ impl AurClient {
         }
 
         let names = package_names.join("&arg[]=");
-        let url = format!("{}?v=5&type=info&arg[]={}", self.base_url, names);
+        let url = format!("{}?v=5&type=info&arg[]={}", self.base_url(), names);
         
         let response = self.client
             .get(&url)
@@ -97,14 +137,16 @@ impl AurClient {
 
         let aur_response: AurResponse = response.json().await?;
         
-        let packages = aur_response.results
+        let packages = aur_response.into_results()?
             .into_iter()
             .map(|aur_pkg| Package {
                 name: aur_pkg.name,
                 version: Some(aur_pkg.version),
                 description: aur_pkg.description,
-                installed: false,
                 source: "aur".to_string(),
+                url: aur_pkg.url,
+                out_of_date: aur_pkg.out_of_date,
+                ..Default::default()
             })
             .collect();
 
@@ -126,6 +168,10 @@ impl AurClient {
                 details.push_str(&format!("Description: {}\n", description));
             }
             
+            if let Some(url) = &package.url {
+                details.push_str(&format!("URL: {}\n", url));
+            }
+            
             details.push_str("\nThis is an AUR (Arch User Repository) package.\n");
             details.push_str("Installation requires building from source.\n");
             
@@ -140,4 +186,29 @@ impl Default for AurClient {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(json: &str) -> Result<usize, String> {
+        let response: AurResponse = serde_json::from_str(json).unwrap();
+        response.into_results().map(|results| results.len()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn too_many_results_reads_as_query_too_broad() {
+        let json = r#"{"resultcount":0,"results":[],"type":"error","version":5,"error":"Too many package results."}"#;
+        assert_eq!(results(json), Err("query too broad".to_string()));
+    }
+
+    #[test]
+    fn responses_without_results_still_parse() {
+        let error = r#"{"resultcount":0,"type":"error","version":5,"error":"Incorrect request type specified."}"#;
+        assert_eq!(results(error), Err("Incorrect request type specified.".to_string()));
+
+        let empty = r#"{"resultcount":0,"type":"search","version":5}"#;
+        assert_eq!(results(empty), Ok(0));
+    }
+}
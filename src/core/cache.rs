@@ -3,6 +3,12 @@ use std::fs;
 use std::path::PathBuf;
 use std::time::UNIX_EPOCH;
 
+// The packages ("source:name") pmux installed in its last install batch, and when
+pub struct LastInstall {
+    pub timestamp: u64,
+    pub packages: Vec<String>,
+}
+
 pub struct Cache {
     cache_dir: PathBuf,
     max_age_hours: u64,
@@ -10,8 +16,7 @@ pub struct Cache {
 
 impl Cache {
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let home = std::env::var("HOME")?;
-        let cache_dir = PathBuf::from(home).join(".cache").join("pmux");
+        let cache_dir = Self::get_cache_dir()?;
         fs::create_dir_all(&cache_dir)?;
         
         Ok(Cache {
@@ -20,6 +25,16 @@ impl Cache {
         })
     }
     
+    pub fn get_cache_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match std::env::var("XDG_CACHE_HOME") {
+            Ok(xdg_cache) if !xdg_cache.is_empty() => Ok(PathBuf::from(xdg_cache).join("pmux")),
+            _ => {
+                let home = std::env::var("HOME")?;
+                Ok(PathBuf::from(home).join(".cache").join("pmux"))
+            }
+        }
+    }
+    
     pub fn is_fresh(&self, pm_name: &str) -> bool {
         let cache_file = self.cache_dir.join(format!("{}_packages.txt", pm_name));
         if !cache_file.exists() {
@@ -70,4 +85,66 @@ impl Cache {
             Ok(vec![])
         }
     }
-}
\ No newline at end of file
+    
+    // Submitted search queries, oldest first, one per line
+    pub fn load_search_history() -> Vec<String> {
+        Self::get_cache_dir()
+            .ok()
+            .and_then(|dir| fs::read_to_string(dir.join("search_history.txt")).ok())
+            .map(|content| content.lines().map(|l| l.to_string()).collect())
+            .unwrap_or_default()
+    }
+    
+    pub fn save_search_history(history: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let cache_dir = Self::get_cache_dir()?;
+        fs::create_dir_all(&cache_dir)?;
+        fs::write(cache_dir.join("search_history.txt"), history.join("\n"))?;
+        Ok(())
+    }
+    
+    // last_install.txt: the unix timestamp on the first line, then one package per line
+    pub fn load_last_install() -> Option<LastInstall> {
+        let content = fs::read_to_string(Self::get_cache_dir().ok()?.join("last_install.txt")).ok()?;
+        let mut lines = content.lines();
+        let timestamp = lines.next()?.trim().parse().ok()?;
+        let packages: Vec<String> = lines.filter(|l| !l.is_empty()).map(|l| l.to_string()).collect();
+        
+        if packages.is_empty() {
+            return None;
+        }
+        Some(LastInstall { timestamp, packages })
+    }
+    
+    pub fn save_last_install(install: &LastInstall) -> Result<(), Box<dyn std::error::Error>> {
+        let cache_dir = Self::get_cache_dir()?;
+        fs::create_dir_all(&cache_dir)?;
+        fs::write(
+            cache_dir.join("last_install.txt"),
+            format!("{}\n{}\n", install.timestamp, install.packages.join("\n")),
+        )?;
+        Ok(())
+    }
+    
+    // install.sh, made executable so it can be run straight from the cache dir
+    pub fn save_install_script(script: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let cache_dir = Self::get_cache_dir()?;
+        fs::create_dir_all(&cache_dir)?;
+        let path = cache_dir.join("install.sh");
+        fs::write(&path, script)?;
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(path)
+    }
+    
+    pub fn clear_last_install() -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::get_cache_dir()?.join("last_install.txt");
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
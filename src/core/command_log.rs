@@ -0,0 +1,47 @@
+use crate::core::cache::Cache;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Set once from the command line (`--log-file <path>`) before any command runs
+static LOG_FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_log_file(path: PathBuf) {
+    let _ = LOG_FILE_OVERRIDE.set(path);
+}
+
+// Audit trail of everything pmux ran on the system, kept next to the package caches
+pub fn get_log_file() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(path) = LOG_FILE_OVERRIDE.get() {
+        return Ok(path.clone());
+    }
+
+    Ok(Cache::get_cache_dir()?.join("commands.log"))
+}
+
+// Append one line per executed command: "<unix secs> [exit <code>] <command>"
+pub fn record(command: &str, status: Option<ExitStatus>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = get_log_file()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let outcome = match status.map(|s| s.code()) {
+        Some(Some(code)) => format!("exit {}", code),
+        Some(None) => "killed by signal".to_string(),
+        None => "failed to start".to_string(),
+    };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{} [{}] {}", timestamp, outcome, command)?;
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
+use crate::core::aur::DEFAULT_AUR_RPC_URL;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// Set once from the command line (`--config <dir>`) before anything reads the config
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -12,25 +18,215 @@ pub struct Config {
     #[serde(rename = "bedrock linux")]
     pub bedrock_linux: Option<HashMap<String, String>>,
     pub pm: PmConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MainConfig {
     pub sudoers: String,
     pub rounded_borders: bool,
+    #[serde(default = "default_terminal_max_lines")]
+    pub terminal_max_lines: usize, // Oldest Terminal pane lines are dropped past this
+    pub theme: Option<String>, // Named palette from THEMES; individual colours still win
+    pub startup_query: Option<String>, // Search filled in at startup unless one is given on the command line
+}
+
+fn default_terminal_max_lines() -> usize {
+    1000
 }
 
+pub const DEFAULT_RESULT_FORMAT: &str = "{selected} {name:38} {installed} {source}";
+
+fn default_result_format() -> String {
+    DEFAULT_RESULT_FORMAT.to_string()
+}
+
+// Built-in palettes: (name, border, highlighted text, text)
+pub const THEMES: &[(&str, &str, &str, &str)] = &[
+    ("dracula", "#bd93f9", "#50fa7b", "#f8f8f2"),
+    ("gruvbox", "#a89984", "#b8bb26", "#ebdbb2"),
+    ("nord", "#81a1c1", "#a3be8c", "#d8dee9"),
+];
+
+const UNITS: &[&str] = &["results_unit", "description_unit", "installed_list_unit", "terminal_unit"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayoutConfig {
     pub right_column_width_percent: u16,  // How much of screen width the right column takes
     pub input_field_height: u16,          // Height in lines for input field
     pub installed_list_percent: u16,      // Percentage of right column height for installed list
     pub terminal_percent: u16,            // Percentage of right column height for terminal
+    #[serde(default = "default_result_format")]
+    pub result_format: String,            // Row template for the results list
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PmConfig {
     pub enabled_pm: Vec<String>,
+    #[serde(default)]
+    pub disabled_pm: Vec<String>, // Detected managers whose packages are hidden from the results
+    #[serde(default = "default_load_timeout_secs")]
+    pub load_timeout_secs: u64, // Listing commands are killed after this long; 0 waits forever
+    #[serde(default)]
+    pub skip_installed: bool, // Don't reinstall selected packages that are already installed
+    #[serde(default)]
+    pub install_batching: InstallBatching, // One install command per manager, or one per package
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallBatching {
+    #[default]
+    Batched,
+    PerPackage,
+}
+
+fn default_load_timeout_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiConfig {
+    #[serde(default)]
+    pub wrap_navigation: bool, // Moving past either end of a list jumps to the other end
+    #[serde(default)]
+    pub scroll_margin: usize, // Rows of context kept above and below the cursor, like vim's scrolloff
+    #[serde(default)]
+    pub latest_version_only: bool, // One result per name within a source, at its highest version
+    #[serde(default)]
+    pub name_only_search: bool, // Start with searches matching package names but not descriptions
+    #[serde(default)]
+    pub empty_query_shows: EmptyQueryShows, // What the Results pane lists before anything is typed
+    #[serde(default)]
+    pub markdown_details: bool, // Render basic markdown (headings, lists, code) in the Details pane
+    #[serde(default)]
+    pub installed_sort: InstalledSort, // Order of the Installed pane
+    #[serde(default)]
+    pub check_updates_on_start: bool, // Count installed packages with a newer version once loading finishes
+    #[serde(default = "default_cursor_symbol")]
+    pub cursor_symbol: String, // Drawn before the highlighted row of the Results and Installed lists
+}
+
+fn default_cursor_symbol() -> String {
+    "> ".to_string()
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            wrap_navigation: false,
+            scroll_margin: 0,
+            latest_version_only: false,
+            name_only_search: false,
+            empty_query_shows: EmptyQueryShows::default(),
+            markdown_details: false,
+            installed_sort: InstalledSort::default(),
+            check_updates_on_start: false,
+            cursor_symbol: default_cursor_symbol(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyQueryShows {
+    #[default]
+    All,
+    Installed,
+    Nothing,
+}
+
+impl EmptyQueryShows {
+    pub fn next(self) -> Self {
+        match self {
+            EmptyQueryShows::All => EmptyQueryShows::Installed,
+            EmptyQueryShows::Installed => EmptyQueryShows::Nothing,
+            EmptyQueryShows::Nothing => EmptyQueryShows::All,
+        }
+    }
+    
+    pub fn describe(self) -> &'static str {
+        match self {
+            EmptyQueryShows::All => "all packages",
+            EmptyQueryShows::Installed => "installed packages",
+            EmptyQueryShows::Nothing => "nothing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstalledSort {
+    #[default]
+    Name,
+    Source,
+    Size,
+}
+
+impl InstalledSort {
+    pub fn next(self) -> Self {
+        match self {
+            InstalledSort::Name => InstalledSort::Source,
+            InstalledSort::Source => InstalledSort::Size,
+            InstalledSort::Size => InstalledSort::Name,
+        }
+    }
+    
+    pub fn describe(self) -> &'static str {
+        match self {
+            InstalledSort::Name => "name",
+            InstalledSort::Source => "source",
+            InstalledSort::Size => "size",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    #[serde(default = "default_aur_rpc_url")]
+    pub aur_rpc_url: String, // AUR RPC endpoint, for a mirror or a caching proxy
+}
+
+fn default_aur_rpc_url() -> String {
+    DEFAULT_AUR_RPC_URL.to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchConfig {
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>, // Package names to always hide; globs, or regexes prefixed "re:"
+}
+
+// An ignore_patterns entry as a regex over the whole package name. Globs support `*` and `?`,
+// e.g. "lib32-*" or "*-doc"; "re:-(dbg|debug)$" is used as a regex as written
+pub fn ignore_pattern_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    if let Some(regex) = pattern.strip_prefix("re:") {
+        return Regex::new(regex);
+    }
+    
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex)
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            aur_rpc_url: default_aur_rpc_url(),
+        }
+    }
 }
 
 impl Default for Config {
@@ -55,35 +251,122 @@ impl Default for Config {
             main: MainConfig {
                 sudoers: "sudo".to_string(),
                 rounded_borders: false,
+                terminal_max_lines: default_terminal_max_lines(),
+                theme: None,
+                startup_query: None,
             },
             layout: LayoutConfig {
                 right_column_width_percent: 30,
                 input_field_height: 3,
                 installed_list_percent: 50,
                 terminal_percent: 50,
+                result_format: default_result_format(),
             },
             border_colours,
             text_colours,
             bedrock_linux: None, // Will be populated if user configures it
             pm: PmConfig {
                 enabled_pm: vec!["nix".to_string(), "paru".to_string(), "emerge".to_string(), "dnf".to_string(), "pacman".to_string()],
+                disabled_pm: Vec::new(),
+                load_timeout_secs: default_load_timeout_secs(),
+                skip_installed: false,
+                install_batching: InstallBatching::Batched,
             },
+            ui: UiConfig::default(),
+            network: NetworkConfig::default(),
+            search: SearchConfig::default(),
         }
     }
 }
 
 impl Config {
+    // Without a config.toml this is the defaults; nothing is written until the first-run
+    // setup saves its choices
     pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
         let config_path = Self::get_config_path()?;
         
         if config_path.exists() {
             let content = fs::read_to_string(config_path)?;
-            let config: Config = toml::from_str(&content)?;
+            let mut config: Config = toml::from_str(&content)?;
+            config.apply_theme();
             Ok(config)
         } else {
-            let default_config = Config::default();
-            default_config.save()?;
-            Ok(default_config)
+            Ok(Config::default())
+        }
+    }
+    
+    pub fn exists() -> bool {
+        Self::get_config_path().is_ok_and(|path| path.exists())
+    }
+    
+    // Fill border_colours/text_colours from the named theme. A colour the user changed
+    // from its default is treated as an override and left alone
+    pub fn apply_theme(&mut self) {
+        let Some(name) = self.main.theme.as_deref() else {
+            return;
+        };
+        let Some(&(_, border, highlight, text)) = THEMES.iter().find(|(theme, ..)| theme.eq_ignore_ascii_case(name)) else {
+            log::warn!("Unknown theme '{}', keeping configured colours", name);
+            return;
+        };
+        
+        let defaults = Config::default();
+        for unit in UNITS {
+            Self::apply_colour(&mut self.border_colours, &defaults.border_colours, unit.to_string(), border);
+            Self::apply_colour(&mut self.text_colours, &defaults.text_colours, format!("{}_highlight_text", unit), highlight);
+            Self::apply_colour(&mut self.text_colours, &defaults.text_colours, format!("{}_text", unit), text);
+        }
+    }
+    
+    fn apply_colour(colours: &mut HashMap<String, String>, defaults: &HashMap<String, String>, key: String, value: &str) {
+        let overridden = colours.get(&key).is_some_and(|current| defaults.get(&key) != Some(current));
+        if !overridden {
+            colours.insert(key, value.to_string());
+        }
+    }
+    
+    // Catch layouts that would render as a broken screen, reporting every problem at once
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut problems = Vec::new();
+        let layout = &self.layout;
+        
+        if layout.right_column_width_percent > 100 {
+            problems.push(format!(
+                "[layout] right_column_width_percent is {}, must be at most 100",
+                layout.right_column_width_percent
+            ));
+        }
+        if layout.installed_list_percent as u32 + layout.terminal_percent as u32 != 100 {
+            problems.push(format!(
+                "[layout] installed_list_percent ({}) and terminal_percent ({}) must add up to 100",
+                layout.installed_list_percent, layout.terminal_percent
+            ));
+        }
+        if layout.input_field_height < 1 {
+            problems.push("[layout] input_field_height must be at least 1".to_string());
+        }
+        if self.pm.enabled_pm.iter().any(|name| name.trim().is_empty()) {
+            problems.push("[pm] enabled_pm contains an empty manager name".to_string());
+        }
+        match reqwest::Url::parse(&self.network.aur_rpc_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => problems.push(format!(
+                "[network] aur_rpc_url \"{}\" must be an http or https URL",
+                self.network.aur_rpc_url
+            )),
+            Err(e) => problems.push(format!("[network] aur_rpc_url \"{}\" is not a valid URL: {}", self.network.aur_rpc_url, e)),
+        }
+        for pattern in &self.search.ignore_patterns {
+            if let Err(e) = ignore_pattern_regex(pattern) {
+                problems.push(format!("[search] ignore_patterns entry \"{}\" is not a valid pattern: {}", pattern, e));
+            }
+        }
+        
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            let config_path = Self::get_config_path()?;
+            Err(format!("invalid config in {}:\n  {}", config_path.display(), problems.join("\n  ")).into())
         }
     }
     
@@ -99,13 +382,73 @@ impl Config {
         Ok(())
     }
     
-    fn get_config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let home = std::env::var("HOME")?;
-        Ok(PathBuf::from(home).join(".config").join("pmux").join("config.toml"))
+    pub fn save_disabled_pm(disabled: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        Self::save_list("pm", "disabled_pm", disabled)
+    }
+    
+    pub fn save_ignore_patterns(patterns: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        Self::save_list("search", "ignore_patterns", patterns)
+    }
+    
+    // Write one list setting back to config.toml. The loaded Config can't be saved as a
+    // whole, since a theme has already been applied to its colours. Only the one key is
+    // touched, so comments and the order of the other settings survive
+    fn save_list(section: &str, key: &str, values: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let config_path = Self::get_config_path()?;
+        let content = match fs::read_to_string(&config_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut document: toml_edit::DocumentMut = content.parse()?;
+        
+        let section_table = document
+            .entry(section)
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .ok_or_else(|| format!("[{}] in the config is not a table", section))?;
+        let mut array = toml_edit::Array::new();
+        for value in values {
+            array.push(value.as_str());
+        }
+        match section_table.get_mut(key).and_then(|item| item.as_value_mut()) {
+            // Keep whatever comment sits before or after the old value
+            Some(existing) => {
+                let decor = existing.decor().clone();
+                *existing = toml_edit::Value::Array(array);
+                *existing.decor_mut() = decor;
+            }
+            None => {
+                section_table.insert(key, toml_edit::value(array));
+            }
+        }
+        
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(config_path, document.to_string())?;
+        Ok(())
+    }
+    
+    pub fn get_config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(Self::get_config_dir()?.join("config.toml"))
+    }
+    
+    pub fn set_config_dir(dir: PathBuf) {
+        let _ = CONFIG_DIR_OVERRIDE.set(dir);
     }
     
     pub fn get_config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let home = std::env::var("HOME")?;
-        Ok(PathBuf::from(home).join(".config").join("pmux"))
+        if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+            return Ok(dir.clone());
+        }
+        
+        match std::env::var("XDG_CONFIG_HOME") {
+            Ok(xdg_config) if !xdg_config.is_empty() => Ok(PathBuf::from(xdg_config).join("pmux")),
+            _ => {
+                let home = std::env::var("HOME")?;
+                Ok(PathBuf::from(home).join(".config").join("pmux"))
+            }
+        }
     }
 }
\ No newline at end of file
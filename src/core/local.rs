@@ -1,7 +1,10 @@
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::fs;
-use crate::core::package_managers::Package;
+use crate::core::package_managers::{binary_in_path, InstallReason, Package, PackageManagerRegistry};
 use crate::core::aur::AurClient;
+use crate::core::pacman_db;
+use crate::core::process;
 
 #[derive(Clone)]
 pub struct LocalPackageManager {
@@ -9,11 +12,21 @@ pub struct LocalPackageManager {
     pub stratum: Option<String>, // For Bedrock Linux
 }
 
+// Managers with hand-written listing code; anything else is driven by its TOML definition
+#[cfg(not(feature = "windows-interop"))]
+pub const BUILTIN_MANAGERS: &[&str] = &["pacman", "paru", "nix", "emerge", "dnf", "apt", "pkg", "opkg", "pkgin"];
+#[cfg(feature = "windows-interop")]
+pub const BUILTIN_MANAGERS: &[&str] = &["pacman", "paru", "nix", "emerge", "dnf", "apt", "pkg", "opkg", "pkgin", "winget"];
+
 impl LocalPackageManager {
     pub fn new(name: String, stratum: Option<String>) -> Self {
         Self { name, stratum }
     }
     
+    pub fn is_builtin(&self) -> bool {
+        BUILTIN_MANAGERS.contains(&self.name.as_str())
+    }
+    
     pub fn list_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
         match self.name.as_str() {
             "pacman" => self.list_pacman_installed(),
@@ -22,6 +35,11 @@ impl LocalPackageManager {
             "emerge" => self.list_portage_installed(),
             "dnf" => self.list_rpm_installed(),
             "apt" => self.list_apt_installed(),
+            "pkg" => self.list_pkg_installed(),
+            "opkg" => self.list_opkg_installed(),
+            "pkgin" => self.list_pkgin_installed(),
+            #[cfg(feature = "windows-interop")]
+            "winget" => self.list_winget_installed(),
             _ => Ok(vec![]),
         }
     }
@@ -34,6 +52,11 @@ impl LocalPackageManager {
             "emerge" => self.list_portage_available(),
             "dnf" => self.list_rpm_available(),
             "apt" => self.list_apt_available(),
+            "pkg" => self.list_pkg_available(),
+            "opkg" => self.list_opkg_available(),
+            "pkgin" => self.list_pkgin_available(),
+            #[cfg(feature = "windows-interop")]
+            "winget" => self.list_winget_available(),
             _ => Ok(vec![]),
         }
     }
@@ -73,6 +96,8 @@ impl LocalPackageManager {
             return Ok(packages);
         }
         
+        let mut depends_by_package = Vec::new();
+        
         for entry in fs::read_dir(&db_path)? {
             let entry = entry?;
             if !entry.file_type()?.is_dir() {
@@ -89,36 +114,53 @@ impl LocalPackageManager {
                     let name = &dir_name_str[..second_last_dash];
                     let version = &dir_name_str[second_last_dash + 1..];
                     
-                    // Read description from desc file
-                    let desc_path = entry.path().join("desc");
-                    let description = if desc_path.exists() {
-                        fs::read_to_string(&desc_path)
-                            .ok()
-                            .and_then(|content| {
-                                // Parse desc file format
-                                let lines: Vec<&str> = content.lines().collect();
-                                for (i, line) in lines.iter().enumerate() {
-                                    if line == &"%DESC%" && i + 1 < lines.len() {
-                                        return Some(lines[i + 1].to_string());
-                                    }
-                                }
-                                None
-                            })
-                    } else {
-                        None
+                    let fields = fs::read_to_string(entry.path().join("desc"))
+                        .map(|content| pacman_db::parse_desc(&content))
+                        .unwrap_or_default();
+                    
+                    // %REASON% is omitted for explicitly installed packages and 1 for dependencies
+                    let install_reason = match pacman_db::first_value(&fields, "REASON") {
+                        Some("1") => InstallReason::Dependency,
+                        _ => InstallReason::Explicit,
                     };
                     
+                    depends_by_package.push(fields.get("DEPENDS").cloned().unwrap_or_default());
                     packages.push(Package {
                         name: name.to_string(),
                         version: Some(version.to_string()),
-                        description,
+                        description: pacman_db::joined_value(&fields, "DESC"),
                         installed: true,
                         source: "pacman".to_string(),
+                        url: pacman_db::first_value(&fields, "URL").map(|u| u.to_string()),
+                        install_reason: Some(install_reason),
+                        provides: pacman_db::provides(&fields),
+                        conflicts: pacman_db::conflicts(&fields),
+                        installed_size: pacman_db::first_value(&fields, "SIZE").and_then(|s| s.parse().ok()),
+                        optional_deps: fields.get("OPTDEPENDS").cloned().unwrap_or_default(),
+                        arch: pacman_db::first_value(&fields, "ARCH").map(|a| a.to_string()),
+                        ..Default::default()
                     });
                 }
             }
         }
         
+        // Invert the dependency lists so each package knows what pulled it in
+        let mut required_by: HashMap<String, Vec<String>> = HashMap::new();
+        for (package, depends) in packages.iter().zip(&depends_by_package) {
+            for dependency in depends {
+                required_by
+                    .entry(pacman_db::strip_version_constraint(dependency).to_string())
+                    .or_default()
+                    .push(package.name.clone());
+            }
+        }
+        for package in &mut packages {
+            if let Some(dependents) = required_by.remove(&package.name) {
+                package.required_by = dependents;
+                package.required_by.sort();
+            }
+        }
+        
         Ok(packages)
     }
     
@@ -131,9 +173,9 @@ impl LocalPackageManager {
     fn list_nix_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
         // Use nix profile list command for now
         // TODO: Parse /nix/var/nix/db/db.sqlite directly
-        let output = std::process::Command::new("nix")
-            .args(&["profile", "list"])
-            .output()?;
+        let output = process::output_with_timeout(
+            std::process::Command::new("nix").args(["profile", "list"])
+        )?;
         
         if !output.status.success() {
             return Ok(vec![]);
@@ -147,10 +189,9 @@ impl LocalPackageManager {
                 if let Some(name) = line.strip_prefix("Name:").map(|s| s.trim()) {
                     packages.push(Package {
                         name: name.to_string(),
-                        version: None,
-                        description: None,
                         installed: true,
                         source: "nix".to_string(),
+                        ..Default::default()
                     });
                 }
             }
@@ -184,26 +225,16 @@ impl LocalPackageManager {
                 let package_dir = package_entry.file_name();
                 let package_dir_str = package_dir.to_string_lossy();
                 
-                // Parse package name from directory (remove version)
-                let package_name = if let Some(dash_pos) = package_dir_str.rfind('-') {
-                    let potential_version = &package_dir_str[dash_pos + 1..];
-                    if potential_version.chars().next().map_or(false, |c| c.is_ascii_digit()) {
-                        &package_dir_str[..dash_pos]
-                    } else {
-                        &package_dir_str
-                    }
-                } else {
-                    &package_dir_str
-                };
+                let (package_name, version) = split_portage_atom(&package_dir_str);
                 
                 let full_name = format!("{}/{}", category_name.to_string_lossy(), package_name);
                 
                 packages.push(Package {
                     name: full_name,
-                    version: None, // TODO: Parse version from directory name
-                    description: None,
+                    version: version.map(|v| v.to_string()),
                     installed: true,
                     source: "emerge".to_string(),
+                    ..Default::default()
                 });
             }
         }
@@ -214,9 +245,9 @@ impl LocalPackageManager {
     fn list_rpm_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
         // Use rpm command for now
         // TODO: Parse /var/lib/rpm/Packages directly
-        let output = std::process::Command::new("rpm")
-            .args(&["-qa", "--queryformat", "%{NAME} %{VERSION}-%{RELEASE} %{SUMMARY}\\n"])
-            .output()?;
+        let output = process::output_with_timeout(
+            std::process::Command::new("rpm").args(["-qa", "--queryformat", "%{NAME} %{VERSION}-%{RELEASE} %{ARCH} %{SIZE} %{SUMMARY}\\n"])
+        )?;
         
         if !output.status.success() {
             return Ok(vec![]);
@@ -226,15 +257,14 @@ impl LocalPackageManager {
         let mut packages = Vec::new();
         
         for line in stdout.lines() {
-            let parts: Vec<&str> = line.splitn(3, ' ').collect();
+            let parts: Vec<&str> = line.splitn(5, ' ').collect();
             if parts.len() >= 2 {
                 let name = parts[0].to_string();
                 let version = Some(parts[1].to_string());
-                let description = if parts.len() >= 3 {
-                    Some(parts[2].to_string())
-                } else {
-                    None
-                };
+                // Packages without compiled code are "noarch"
+                let arch = parts.get(2).map(|arch| arch.to_string());
+                let installed_size = parts.get(3).and_then(|size| size.parse().ok());
+                let description = parts.get(4).map(|summary| summary.to_string());
                 
                 packages.push(Package {
                     name,
@@ -242,6 +272,9 @@ impl LocalPackageManager {
                     description,
                     installed: true,
                     source: "dnf".to_string(),
+                    installed_size,
+                    arch,
+                    ..Default::default()
                 });
             }
         }
@@ -258,90 +291,61 @@ impl LocalPackageManager {
             return Ok(packages);
         }
         
+        // Group name -> member count, collected from each package's %GROUPS%
+        let mut groups: HashMap<String, usize> = HashMap::new();
+        
         // Read sync databases directly (core.db, extra.db, community.db, multilib.db, etc.)
         for entry in fs::read_dir(&sync_path)? {
             let entry = entry?;
             let file_name = entry.file_name();
             let file_name_str = file_name.to_string_lossy();
             
-            if file_name_str.ends_with(".db") {
-                let repo_name = file_name_str.trim_end_matches(".db");
-                let db_path = entry.path();
+            if let Some(repo) = file_name_str.strip_suffix(".db") {
+                // A single unreadable database shouldn't hide the other repos
+                let entries = match pacman_db::read_sync_db(&entry.path()) {
+                    Ok(entries) => entries,
+                    Err(_) => continue,
+                };
                 
-                // Extract and parse the database (it's a tar.xz archive)
-                if let Ok(output) = std::process::Command::new("tar")
-                    .args(&["-tf", &db_path.to_string_lossy()])
-                    .output() {
-                    if output.status.success() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        // Extract all package directories from tar listing
-                        let mut package_dirs = Vec::new();
-                        for line in stdout.lines() {
-                            if line.ends_with("/desc") {
-                                if let Some(pkg_dir) = line.strip_suffix("/desc") {
-                                    package_dirs.push(pkg_dir.to_string());
-                                }
-                            }
-                        }
-                        
-                        // Process each package directory
-                        for pkg_dir in package_dirs {
-                            // Parse package name and version from directory name
-                            // Format: package-name-version-release
-                            let parts: Vec<&str> = pkg_dir.rsplitn(3, '-').collect();
-                            if parts.len() >= 3 {
-                                let name = parts[2];
-                                let version = format!("{}-{}", parts[1], parts[0]);
-                                
-                                packages.push(Package {
-                                    name: name.to_string(),
-                                    version: Some(version),
-                                    description: Some(format!("Package from {} repository", repo_name)),
-                                    installed: false,
-                                    source: "pacman".to_string(),
-                                });
-                            }
-                        }
+                for fields in entries {
+                    for group in fields.get("GROUPS").into_iter().flatten() {
+                        *groups.entry(group.clone()).or_default() += 1;
                     }
-                }
-                
-                // Also try to read the .files database for more complete info
-                let files_db_path = format!("{}/{}.files", sync_path, repo_name);
-                if Path::new(&files_db_path).exists() {
-                    if let Ok(output) = std::process::Command::new("tar")
-                        .args(&["-tf", &files_db_path])
-                        .output() {
-                        if output.status.success() {
-                            let stdout = String::from_utf8_lossy(&output.stdout);
-                            for line in stdout.lines() {
-                                if line.ends_with("/desc") {
-                                    if let Some(pkg_dir) = line.strip_suffix("/desc") {
-                                        // Parse package name (remove version)
-                                        let parts: Vec<&str> = pkg_dir.rsplitn(3, '-').collect();
-                                        if parts.len() >= 3 {
-                                            let name = parts[2];
-                                            let version = format!("{}-{}", parts[1], parts[0]);
-                                            
-                                            // Only add if not already added from .db file
-                                            if !packages.iter().any(|p| p.name == name) {
-                                                packages.push(Package {
-                                                    name: name.to_string(),
-                                                    version: Some(version),
-                                                    description: Some(format!("Package from {} repository", repo_name)),
-                                                    installed: false,
-                                                    source: "pacman".to_string(),
-                                                });
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                    
+                    if let Some(name) = pacman_db::first_value(&fields, "NAME") {
+                        packages.push(Package {
+                            name: name.to_string(),
+                            version: pacman_db::first_value(&fields, "VERSION").map(|v| v.to_string()),
+                            description: pacman_db::joined_value(&fields, "DESC"),
+                            source: "pacman".to_string(),
+                            url: pacman_db::first_value(&fields, "URL").map(|u| u.to_string()),
+                            provides: pacman_db::provides(&fields),
+                            repo: Some(repo.to_string()),
+                            conflicts: pacman_db::conflicts(&fields),
+                            installed_size: pacman_db::first_value(&fields, "ISIZE").and_then(|s| s.parse().ok()),
+                            download_size: pacman_db::first_value(&fields, "CSIZE").and_then(|s| s.parse().ok()),
+                            optional_deps: fields.get("OPTDEPENDS").cloned().unwrap_or_default(),
+                            arch: pacman_db::first_value(&fields, "ARCH").map(|a| a.to_string()),
+                            ..Default::default()
+                        });
                     }
                 }
             }
         }
         
+        // Groups show up as their own entries so `base-devel` and friends are searchable
+        let mut groups: Vec<(String, usize)> = groups.into_iter().collect();
+        groups.sort();
+        for (group, members) in groups {
+            packages.push(Package {
+                name: group,
+                description: Some(format!("Package group ({} members)", members)),
+                source: "pacman".to_string(),
+                is_group: true,
+                ..Default::default()
+            });
+        }
+        
         Ok(packages)
     }
     
@@ -369,10 +373,9 @@ impl LocalPackageManager {
                             // This might be a package name
                             packages.push(Package {
                                 name: line.to_string(),
-                                version: None,
                                 description: Some("AUR package".to_string()),
-                                installed: false,
                                 source: "paru".to_string(),
+                                ..Default::default()
                             });
                         }
                     }
@@ -426,8 +429,8 @@ impl LocalPackageManager {
                     name: pkg_name.to_string(),
                     version: Some("latest".to_string()),
                     description: Some(format!("AUR package: {}", pkg_name)),
-                    installed: false,
                     source: "paru".to_string(),
+                    ..Default::default()
                 });
             }
         }
@@ -480,8 +483,8 @@ impl LocalPackageManager {
                 name: pkg_name.to_string(),
                 version: Some("nixpkgs-unstable".to_string()),
                 description: Some(format!("Nix package: {}", pkg_name)),
-                installed: false,
                 source: "nix".to_string(),
+                ..Default::default()
             });
         }
         
@@ -523,10 +526,8 @@ impl LocalPackageManager {
                 
                 packages.push(Package {
                     name: full_name,
-                    version: None,
-                    description: None,
-                    installed: false,
                     source: "emerge".to_string(),
+                    ..Default::default()
                 });
             }
         }
@@ -571,8 +572,9 @@ impl LocalPackageManager {
                                     name: format!("{}", pkg_name),
                                     version: Some("latest".to_string()),
                                     description: Some(format!("Package from {} repository", repo_name)),
-                                    installed: false,
                                     source: "dnf".to_string(),
+                                    repo: Some(repo_name.to_string()),
+                                    ..Default::default()
                                 });
                             }
                         }
@@ -594,8 +596,8 @@ impl LocalPackageManager {
                     name: pkg_name.to_string(),
                     version: Some("latest".to_string()),
                     description: Some(format!("Common Fedora package: {}", pkg_name)),
-                    installed: false,
                     source: "dnf".to_string(),
+                    ..Default::default()
                 });
             }
         }
@@ -606,14 +608,21 @@ impl LocalPackageManager {
     fn list_apt_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
         let mut packages = Vec::new();
         
-        let output = std::process::Command::new("dpkg-query")
-            .args(&["-W", "-f=${Package}\t${Version}\t${Status}\n"])
-            .output()?;
+        let output = process::output_with_timeout(
+            std::process::Command::new("dpkg-query").args(["-W", "-f=${Package}\t${Version}\t${Status}\t${Provides}\t${Conflicts}\t${Installed-Size}\t${Architecture}\n"])
+        )?;
             
         if !output.status.success() {
             return Ok(packages);
         }
         
+        // Packages the user asked for; everything else was pulled in as a dependency
+        let manual: HashSet<String> = process::output_with_timeout(std::process::Command::new("apt-mark").arg("showmanual"))
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).lines().map(|l| l.trim().to_string()).collect())
+            .unwrap_or_default();
+        
         let stdout = String::from_utf8_lossy(&output.stdout);
         for line in stdout.lines() {
             let parts: Vec<&str> = line.split('\t').collect();
@@ -621,15 +630,31 @@ impl LocalPackageManager {
                 let name = parts[0];
                 let version = parts[1];
                 let status = parts[2];
+                let provides = parts.get(3).map(|p| parse_deb_relation(p)).unwrap_or_default();
+                let conflicts = parts.get(4).map(|c| parse_deb_conflicts(c)).unwrap_or_default();
+                // Installed-Size is in KiB
+                let installed_size = parts.get(5).and_then(|s| s.trim().parse::<u64>().ok()).map(|kib| kib * 1024);
+                let arch = parts.get(6).map(|a| a.trim()).filter(|a| !a.is_empty()).map(|a| a.to_string());
                 
                 // Only include installed packages
                 if status.contains("install ok installed") {
                     packages.push(Package {
                         name: name.to_string(),
                         version: Some(version.to_string()),
-                        description: None,
                         installed: true,
                         source: "apt".to_string(),
+                        install_reason: if manual.is_empty() {
+                            None
+                        } else if manual.contains(name) {
+                            Some(InstallReason::Explicit)
+                        } else {
+                            Some(InstallReason::Dependency)
+                        },
+                        provides,
+                        conflicts,
+                        installed_size,
+                        arch,
+                        ..Default::default()
                     });
                 }
             }
@@ -641,36 +666,420 @@ impl LocalPackageManager {
     fn list_apt_available(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
         let mut packages = Vec::new();
         
-        let output = std::process::Command::new("apt-cache")
-            .args(&["search", ".*"])
-            .output()?;
+        let output = process::output_with_timeout(
+            std::process::Command::new("apt-cache").args(["search", ".*"])
+        )?;
             
         if !output.status.success() {
             return Ok(packages);
         }
         
+        // `apt-cache search` doesn't report Provides, Conflicts or sizes, so pick them up from the package lists
+        let mut entries_by_name = read_apt_lists();
+        
         let stdout = String::from_utf8_lossy(&output.stdout);
         for line in stdout.lines() {
             if let Some(dash_pos) = line.find(" - ") {
                 let name = &line[..dash_pos];
                 let description = &line[dash_pos + 3..];
+                let entry = entries_by_name.remove(name).unwrap_or_default();
                 
                 packages.push(Package {
                     name: name.to_string(),
-                    version: None,
                     description: Some(description.to_string()),
-                    installed: false,
                     source: "apt".to_string(),
+                    provides: entry.provides,
+                    conflicts: entry.conflicts,
+                    installed_size: entry.installed_size,
+                    download_size: entry.download_size,
+                    arch: entry.arch,
+                    ..Default::default()
                 });
             }
         }
         
         Ok(packages)
     }
+    
+    fn list_pkg_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("pkg").arg("info")
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(|line| parse_pkg_line(line, true)).collect())
+    }
+    
+    fn list_pkg_available(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("pkg").args(["search", "-g", "*"])
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(|line| parse_pkg_line(line, false)).collect())
+    }
+    
+    fn list_opkg_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("opkg").arg("list-installed")
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(|line| parse_opkg_line(line, true)).collect())
+    }
+    
+    fn list_opkg_available(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("opkg").arg("list")
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(|line| parse_opkg_line(line, false)).collect())
+    }
+    
+    fn list_pkgin_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("pkgin").args(["-p", "list"])
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(|line| parse_pkgin_line(line, true)).collect())
+    }
+    
+    fn list_pkgin_available(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("pkgin").args(["-p", "avail"])
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(|line| parse_pkgin_line(line, false)).collect())
+    }
+    
+    #[cfg(feature = "windows-interop")]
+    fn list_winget_installed(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("winget.exe").args(["list", "--accept-source-agreements"])
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        Ok(parse_winget_table(&String::from_utf8_lossy(&output.stdout), true))
+    }
+    
+    // winget has no "list everything" command; an empty query searches the whole catalogue
+    #[cfg(feature = "windows-interop")]
+    fn list_winget_available(&self) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let output = process::output_with_timeout(
+            std::process::Command::new("winget.exe")
+                .args(["search", "--query", "", "--source", "winget", "--accept-source-agreements"])
+        )?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        
+        Ok(parse_winget_table(&String::from_utf8_lossy(&output.stdout), false))
+    }
+}
+
+// `winget list` and `winget search` print a table sized to its content, so the header says
+// where each column starts. Headers are translated, so columns are taken by position:
+//   Name             Id               Version      Available  Source
+//   ---------------------------------------------------------------
+//   Microsoft Edge   Microsoft.Edge   119.0.2151   120.0.2210 winget
+// Packages are named by Id, which is what `winget install --id` takes. When the output is
+// piped, winget cuts columns that don't fit short with a trailing "…"; a cut Id can't be
+// installed or removed, so those rows are left out. Built for tests too, so its test runs
+// without the feature
+#[cfg(any(test, feature = "windows-interop"))]
+fn parse_winget_table(output: &str, installed: bool) -> Vec<Package> {
+    // Progress spinners are redrawn over the same line with \r before the table starts
+    let lines: Vec<Vec<char>> = output
+        .lines()
+        .map(|line| line.rsplit('\r').next().unwrap_or(line).chars().collect())
+        .collect();
+    let Some(rule) = lines.iter().position(|line| line.len() > 3 && line.iter().all(|&c| c == '-')) else {
+        return Vec::new();
+    };
+    let Some(header) = rule.checked_sub(1).map(|index| &lines[index]) else {
+        return Vec::new();
+    };
+    
+    let starts: Vec<usize> = (0..header.len())
+        .filter(|&i| header[i] != ' ' && (i == 0 || header[i - 1] == ' '))
+        .collect();
+    if starts.len() < 3 {
+        return Vec::new();
+    }
+    let field = |line: &[char], column: usize| -> String {
+        let start = starts[column].min(line.len());
+        let end = starts.get(column + 1).copied().unwrap_or(line.len()).min(line.len());
+        line[start..end].iter().collect::<String>().trim().to_string()
+    };
+    
+    lines[rule + 1..]
+        .iter()
+        .filter_map(|line| {
+            let id = field(line, 1);
+            if id.is_empty() {
+                return None;
+            }
+            if id.ends_with('…') {
+                log::warn!("Skipping winget package with a truncated Id: {}", id);
+                return None;
+            }
+            let name = field(line, 0).trim_end_matches('…').to_string();
+            // A cut version is no use for comparing, so it's treated as unknown
+            let version = Some(field(line, 2)).filter(|version| !version.ends_with('…'));
+            
+            Some(Package {
+                name: id,
+                version: version.filter(|version| !version.is_empty()),
+                description: if name.is_empty() { None } else { Some(name) },
+                installed,
+                source: "winget".to_string(),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+// Parse one line of `pkg info` / `pkg search` output. The default format is
+// "name-version   comment"; `pkg search -Q comment` style output is "name: comment"
+fn parse_pkg_line(line: &str, installed: bool) -> Option<Package> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    
+    let (token, description) = match line.split_once(char::is_whitespace) {
+        Some((token, rest)) => (token, rest.trim()),
+        None => (line, ""),
+    };
+    
+    let (name, version) = if let Some(name) = token.strip_suffix(':') {
+        (name, None)
+    } else {
+        // Versions never contain '-', so the last dash splits name from version
+        match token.rsplit_once('-') {
+            Some((name, version)) if !name.is_empty() => (name, Some(version.to_string())),
+            _ => (token, None),
+        }
+    };
+    
+    Some(Package {
+        name: name.to_string(),
+        version,
+        description: if description.is_empty() { None } else { Some(description.to_string()) },
+        installed,
+        source: "pkg".to_string(),
+        ..Default::default()
+    })
+}
+
+// Parse one line of `opkg list-installed` ("name - version") or `opkg list`
+// ("name - version - description"). Descriptions may contain " - " themselves
+fn parse_opkg_line(line: &str, installed: bool) -> Option<Package> {
+    let mut fields = line.splitn(3, " - ");
+    let name = fields.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let version = fields.next().map(str::trim).filter(|v| !v.is_empty());
+    let description = fields.next().map(str::trim).filter(|d| !d.is_empty());
+    
+    Some(Package {
+        name: name.to_string(),
+        version: version.map(str::to_string),
+        description: description.map(str::to_string),
+        installed,
+        source: "opkg".to_string(),
+        ..Default::default()
+    })
+}
+
+// Parse one line of `pkgin -p list` / `pkgin -p avail` ("name-version;comment"). pkgsrc
+// versions never contain '-' (revisions are written "1.2nb3"), so the last dash splits
+// name from version. Output without -p separates the comment with spaces instead
+fn parse_pkgin_line(line: &str, installed: bool) -> Option<Package> {
+    let line = line.trim();
+    let (token, description) = line
+        .split_once(';')
+        .or_else(|| line.split_once(char::is_whitespace))
+        .map_or((line, ""), |(token, rest)| (token, rest.trim()));
+    let (name, version) = token.rsplit_once('-').filter(|(name, _)| !name.is_empty())?;
+    
+    Some(Package {
+        name: name.to_string(),
+        version: Some(version.to_string()),
+        description: if description.is_empty() { None } else { Some(description.to_string()) },
+        installed,
+        source: "pkgin".to_string(),
+        ..Default::default()
+    })
+}
+
+// Debian "Provides: foo (= 1.0), bar" -> ["foo", "bar"]
+fn parse_deb_relation(relation: &str) -> Vec<String> {
+    relation
+        .split(',')
+        .map(|p| p.split('(').next().unwrap_or("").trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+// Debian "Conflicts: foo, bar (<< 2.0)" -> ["foo"]. Versioned conflicts almost always
+// target old releases and can't be judged without comparing versions, so they're skipped
+fn parse_deb_conflicts(conflicts: &str) -> Vec<String> {
+    conflicts
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty() && !c.contains('('))
+        .map(|c| c.to_string())
+        .collect()
+}
+
+#[derive(Default)]
+struct AptListEntry {
+    provides: Vec<String>,
+    conflicts: Vec<String>,
+    installed_size: Option<u64>,
+    download_size: Option<u64>,
+    arch: Option<String>,
+}
+
+// Package name -> Provides/Conflicts, sizes and architecture from the downloaded apt indexes (/var/lib/apt/lists/*_Packages).
+// On multiarch systems a name is listed once per architecture; the native (or "all") entry is kept
+fn read_apt_lists() -> HashMap<String, AptListEntry> {
+    let mut entries_by_name: HashMap<String, AptListEntry> = HashMap::new();
+    let Ok(entries) = fs::read_dir("/var/lib/apt/lists") else {
+        return entries_by_name;
+    };
+    let native_arch = process::output_with_timeout(
+        std::process::Command::new("dpkg").arg("--print-architecture")
+    )
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    let is_native = |entry: &AptListEntry| match (&entry.arch, &native_arch) {
+        (Some(arch), Some(native)) => arch == "all" || arch == native,
+        _ => false,
+    };
+    
+    let mut add = |name: String, entry: AptListEntry| {
+        let replace = entries_by_name
+            .get(&name)
+            .is_none_or(|existing| !is_native(existing) && is_native(&entry));
+        if replace {
+            entries_by_name.insert(name, entry);
+        }
+    };
+    
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().ends_with("_Packages") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        
+        let mut current: Option<(String, AptListEntry)> = None;
+        for line in content.lines() {
+            if let Some(value) = line.strip_prefix("Package: ") {
+                if let Some((name, entry)) = current.take() {
+                    add(name, entry);
+                }
+                current = Some((value.trim().to_string(), AptListEntry::default()));
+                continue;
+            }
+            let Some((_, entry)) = &mut current else {
+                continue;
+            };
+            if let Some(value) = line.strip_prefix("Provides: ") {
+                entry.provides = parse_deb_relation(value);
+            } else if let Some(value) = line.strip_prefix("Conflicts: ") {
+                entry.conflicts = parse_deb_conflicts(value);
+            } else if let Some(value) = line.strip_prefix("Installed-Size: ") {
+                // In KiB, unlike Size which is in bytes
+                entry.installed_size = value.trim().parse::<u64>().ok().map(|kib| kib * 1024);
+            } else if let Some(value) = line.strip_prefix("Size: ") {
+                entry.download_size = value.trim().parse().ok();
+            } else if let Some(value) = line.strip_prefix("Architecture: ") {
+                entry.arch = Some(value.trim().to_string());
+            }
+        }
+        if let Some((name, entry)) = current {
+            add(name, entry);
+        }
+    }
+    
+    entries_by_name
+}
+
+// "gtk+-3.24.41-r1" -> ("gtk+", Some("3.24.41-r1")). The version is the last "-"-separated
+// part starting with a digit, plus a trailing "-rN" revision if there is one
+fn split_portage_atom(atom: &str) -> (&str, Option<&str>) {
+    let without_revision = match atom.rsplit_once('-') {
+        Some((rest, revision)) if revision.len() > 1
+            && revision.starts_with('r')
+            && revision[1..].bytes().all(|b| b.is_ascii_digit()) => rest,
+        _ => atom,
+    };
+    
+    match without_revision.rfind('-') {
+        Some(dash) if without_revision[dash + 1..].starts_with(|c: char| c.is_ascii_digit()) => {
+            (&atom[..dash], Some(&atom[dash + 1..]))
+        }
+        _ => (atom, None),
+    }
+}
+
+// FreeBSD's pkg keeps its database in /var/db/pkg as well, which would otherwise
+// look like a Gentoo system
+fn is_freebsd_pkg_db(base_path: &str) -> bool {
+    Path::new(&format!("{}/var/db/pkg/local.sqlite", base_path)).exists()
 }
 
 use crate::core::config::Config;
 
+// What standard (non-Bedrock) detection looks for, shown when nothing is found
+pub const DETECTION_HINTS: &[(&str, &str)] = &[
+    ("pacman", "/var/lib/pacman"),
+    ("dnf", "/var/lib/rpm"),
+    ("emerge", "/var/db/pkg"),
+    ("nix", "/nix/var/nix/db"),
+    ("apt", "/var/lib/dpkg/status"),
+    ("pkg", "/var/db/pkg/local.sqlite"),
+    ("opkg", "/usr/lib/opkg"),
+    ("pkgin", "/var/db/pkgin"),
+];
+
 pub fn detect_package_managers_with_config(config: &Config) -> Vec<LocalPackageManager> {
     let mut managers = Vec::new();
     
@@ -771,7 +1180,7 @@ pub fn detect_package_managers_with_config(config: &Config) -> Vec<LocalPackageM
             managers.push(LocalPackageManager::new("dnf".to_string(), None));
         }
         
-        if Path::new("/var/db/pkg").exists() {
+        if Path::new("/var/db/pkg").exists() && !is_freebsd_pkg_db("") {
             managers.push(LocalPackageManager::new("emerge".to_string(), None));
         }
         
@@ -783,14 +1192,162 @@ pub fn detect_package_managers_with_config(config: &Config) -> Vec<LocalPackageM
         if Path::new("/var/lib/dpkg/status").exists() {
             managers.push(LocalPackageManager::new("apt".to_string(), None));
         }
+        
+        // Check for pkg (FreeBSD)
+        if is_freebsd_pkg_db("") || (cfg!(target_os = "freebsd") && binary_in_path("pkg")) {
+            managers.push(LocalPackageManager::new("pkg".to_string(), None));
+        }
+        
+        // Check for opkg (OpenWrt and other embedded systems)
+        if Path::new("/usr/lib/opkg").exists() || binary_in_path("opkg") {
+            managers.push(LocalPackageManager::new("opkg".to_string(), None));
+        }
+        
+        // Check for pkgin (NetBSD and other pkgsrc systems)
+        if Path::new("/var/db/pkgin").exists() || binary_in_path("pkgin") {
+            managers.push(LocalPackageManager::new("pkgin".to_string(), None));
+        }
+        
+        // Check for winget (Windows, reached from WSL through interop)
+        if cfg!(feature = "windows-interop") && binary_in_path("winget.exe") {
+            managers.push(LocalPackageManager::new("winget".to_string(), None));
+        }
     }
     
     managers
 }
 
+// Add managers defined in pkgmanagers/*.toml that declare how to detect themselves
+pub fn detect_registry_managers(registry: &PackageManagerRegistry, managers: &mut Vec<LocalPackageManager>) {
+    let mut names: Vec<&String> = registry.managers.keys().collect();
+    names.sort();
+    
+    for name in names {
+        if managers.iter().any(|m| &m.name == name) {
+            continue;
+        }
+        
+        let config = &registry.managers[name];
+        let path_found = config.detect_path.as_ref().is_some_and(|path| Path::new(path).exists());
+        let binary_found = config.detect_binary.as_ref().is_some_and(|binary| binary_in_path(binary));
+        
+        if path_found || binary_found {
+            managers.push(LocalPackageManager::new(name.clone(), None));
+        }
+    }
+}
+
 // Backward compatibility function
 pub fn detect_package_managers() -> Vec<LocalPackageManager> {
     // Use default config for detection
     let config = Config::default();
     detect_package_managers_with_config(&config)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Name, version, description and whether it's installed
+    type Fields = (String, Option<String>, Option<String>, bool);
+
+    fn fields(package: Option<Package>) -> Option<Fields> {
+        package.map(|p| (p.name, p.version, p.description, p.installed))
+    }
+
+    fn expected(name: &str, version: Option<&str>, description: Option<&str>, installed: bool) -> Option<Fields> {
+        Some((name.to_string(), version.map(str::to_string), description.map(str::to_string), installed))
+    }
+
+    #[test]
+    fn parse_pkg_line_reads_installed_and_remote_lines() {
+        assert_eq!(
+            fields(parse_pkg_line("curl-8.6.0                     Command line tool and library for transferring data with URLs", true)),
+            expected("curl", Some("8.6.0"), Some("Command line tool and library for transferring data with URLs"), true)
+        );
+        assert_eq!(
+            fields(parse_pkg_line("py39-setuptools-63.1.0_1       Python packages installer", false)),
+            expected("py39-setuptools", Some("63.1.0_1"), Some("Python packages installer"), false)
+        );
+        assert_eq!(
+            fields(parse_pkg_line("vim: Improved version of the vi editor", false)),
+            expected("vim", None, Some("Improved version of the vi editor"), false)
+        );
+        assert_eq!(fields(parse_pkg_line("pkg-1.21.3", true)), expected("pkg", Some("1.21.3"), None, true));
+        assert_eq!(fields(parse_pkg_line("   ", false)), None);
+    }
+
+    #[test]
+    fn parse_opkg_line_keeps_dashes_in_the_description() {
+        assert_eq!(
+            fields(parse_opkg_line("busybox - 1.36.1-1", true)),
+            expected("busybox", Some("1.36.1-1"), None, true)
+        );
+        assert_eq!(
+            fields(parse_opkg_line("luci-app-firewall - 24.086.45142~09d5a38 - Firewall and Portforwarding application", false)),
+            expected("luci-app-firewall", Some("24.086.45142~09d5a38"), Some("Firewall and Portforwarding application"), false)
+        );
+        assert_eq!(
+            fields(parse_opkg_line("curl - 8.5.0-1 - A client-side URL transfer tool - with TLS support", false)),
+            expected("curl", Some("8.5.0-1"), Some("A client-side URL transfer tool - with TLS support"), false)
+        );
+        assert_eq!(fields(parse_opkg_line("kmod-nft-core", false)), expected("kmod-nft-core", None, None, false));
+        assert_eq!(fields(parse_opkg_line(" - 1.0", false)), None);
+    }
+
+    #[test]
+    fn parse_winget_table_takes_columns_from_the_header() {
+        let output = "\r   - \r   \\ \rName                 Id                        Version        Available  Source
+------------------------------------------------------------------------------------
+Microsoft Edge       Microsoft.Edge            119.0.2151.97  120.0.2210 winget
+7-Zip 23.01 (x64)    7zip.7zip                 23.01                     winget
+Visual Studio Code…  Microsoft.VisualStudioCo… 1.85.1                    winget
+Some Long Version    Example.LongVersion       2024.01.15.1…             winget
+";
+        let packages: Vec<(String, Option<String>, Option<String>)> = parse_winget_table(output, true)
+            .into_iter()
+            .map(|p| (p.name, p.version, p.description))
+            .collect();
+        let row = |id: &str, version: Option<&str>, name: &str| {
+            (id.to_string(), version.map(str::to_string), Some(name.to_string()))
+        };
+        assert_eq!(packages, vec![
+            row("Microsoft.Edge", Some("119.0.2151.97"), "Microsoft Edge"),
+            row("7zip.7zip", Some("23.01"), "7-Zip 23.01 (x64)"),
+            row("Example.LongVersion", None, "Some Long Version"),
+        ]);
+        assert!(parse_winget_table("No installed package found matching input criteria.", false).is_empty());
+    }
+
+    #[test]
+    fn parse_pkgin_line_splits_at_the_last_dash() {
+        assert_eq!(
+            fields(parse_pkgin_line("py311-requests-2.31.0nb1;HTTP library for Python", true)),
+            expected("py311-requests", Some("2.31.0nb1"), Some("HTTP library for Python"), true)
+        );
+        assert_eq!(
+            fields(parse_pkgin_line("vim-9.0.2092   Vim editor (vi clone)", false)),
+            expected("vim", Some("9.0.2092"), Some("Vim editor (vi clone)"), false)
+        );
+        assert_eq!(fields(parse_pkgin_line("mozilla-rootcerts-1.0.20230505", false)), expected("mozilla-rootcerts", Some("1.0.20230505"), None, false));
+        assert_eq!(fields(parse_pkgin_line("nodash;no version here", false)), None);
+        assert_eq!(fields(parse_pkgin_line("-1.0;empty name", false)), None);
+    }
+
+    #[test]
+    fn split_portage_atom_separates_name_version_and_revision() {
+        let cases = [
+            ("vim-9.0.1403", ("vim", Some("9.0.1403"))),
+            ("vim-9.0-r1", ("vim", Some("9.0-r1"))),
+            ("gtk+-3.24.41-r12", ("gtk+", Some("3.24.41-r12"))),
+            ("font-misc-misc-1.1.3", ("font-misc-misc", Some("1.1.3"))),
+            ("libsigc++-2-2.12.1", ("libsigc++-2", Some("2.12.1"))),
+            ("=app-editors/vim-9.0-r1", ("=app-editors/vim", Some("9.0-r1"))),
+            ("perl-r1", ("perl-r1", None)),
+            ("vim", ("vim", None)),
+        ];
+        for (atom, expected) in cases {
+            assert_eq!(split_portage_atom(atom), expected, "{}", atom);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::core::cache::Cache;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Debug output goes to a file under the cache dir, never to stderr, so it can't
+// interleave with the TUI on the alternate screen
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{} [{}] {}: {}", timestamp, record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Logging is off unless `--verbose` is passed or `RUST_LOG` names a level
+pub fn init(verbose: bool) {
+    let level = if verbose {
+        LevelFilter::Debug
+    } else {
+        std::env::var("RUST_LOG")
+            .ok()
+            .and_then(|value| LevelFilter::from_str(value.trim()).ok())
+            .unwrap_or(LevelFilter::Off)
+    };
+
+    if level == LevelFilter::Off {
+        return;
+    }
+
+    let Ok(cache_dir) = Cache::get_cache_dir() else {
+        return;
+    };
+    if fs::create_dir_all(&cache_dir).is_err() {
+        return;
+    }
+
+    let file = match OpenOptions::new().create(true).append(true).open(cache_dir.join("pmux.log")) {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+
+    if log::set_boxed_logger(Box::new(FileLogger { file: Mutex::new(file) })).is_ok() {
+        log::set_max_level(level);
+    }
+}
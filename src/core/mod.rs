@@ -3,5 +3,10 @@ pub mod package_managers;
 pub mod cache;
 pub mod api;
 pub mod local;
+pub mod logging;
+pub mod command_log;
+pub mod pacman_db;
+pub mod process;
 pub mod unified;
+pub mod version;
 pub mod aur;
\ No newline at end of file
@@ -3,16 +3,34 @@ use std::collections::HashMap;
 use std::process::Command;
 use regex::Regex;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Package {
     pub name: String,
     pub version: Option<String>,
     pub description: Option<String>,
     pub installed: bool,
     pub source: String,
+    pub url: Option<String>, // Upstream project homepage
+    pub install_reason: Option<InstallReason>, // Only known for installed packages
+    pub required_by: Vec<String>, // Installed packages that depend on this one
+    pub is_group: bool, // A pacman group standing in for its member packages
+    pub provides: Vec<String>, // Virtual names this package satisfies (e.g. "sh", "java-runtime")
+    pub repo: Option<String>, // Repository it comes from (core/extra, fedora/updates), where known
+    pub conflicts: Vec<String>, // Packages (or virtual names) that can't be installed alongside this one
+    pub installed_size: Option<u64>, // Bytes on disk once installed, where known
+    pub download_size: Option<u64>, // Bytes downloaded to install it, where known
+    pub out_of_date: Option<u64>, // AUR: when the package was flagged out-of-date, as a Unix timestamp
+    pub optional_deps: Vec<String>, // Optional companions as "name: what for" (pacman %OPTDEPENDS%)
+    pub arch: Option<String>, // CPU architecture it's built for (x86_64, i386, any), where known
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InstallReason {
+    Explicit,
+    Dependency,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PackageManagerConfig {
     pub name: String,
     pub display_name: String,
@@ -21,11 +39,36 @@ pub struct PackageManagerConfig {
     pub list_installed_cmd: String,
     pub search_cmd: String,
     pub install_cmd: String,
+    pub preview_cmd: Option<String>, // Dry run of install_cmd listing what would be pulled in, e.g. "emerge --pretend {}"
+    pub remove_cmd: Option<String>, // Used to undo pmux's last install; without it those packages can't be undone
+    pub version_install_format: Option<String>, // How one pinned package is written, e.g. "{name}={version}"
+    pub downgrade_cmd: Option<String>, // Installs an older version, given as version_install_format, e.g. "dnf downgrade {}"
+    pub orphans_cmd: Option<String>, // Prints installed packages nothing depends on any more, one name per line
     pub requires_root: bool,
     pub package_separator: String,
     pub installed_indicator: Option<String>,
+    #[serde(default)]
+    pub installed_indicator_is_regex: bool, // Match installed_indicator as a regex rather than a substring
     pub cleanup_regex: Option<String>,
     pub version_regex: Option<String>,
+    pub name_field: Option<usize>, // Which field of each line is the name, counting from 0
+    pub version_field: Option<usize>, // Which field is the version
+    pub field_separator: Option<String>, // What splits fields for name_field/version_field; runs of whitespace if unset
+    #[serde(default)]
+    pub output_format: OutputFormat, // How the list and search commands print packages
+    pub json_name_path: Option<String>, // With output_format = "json": where names are, e.g. "packages[*].name"
+    pub json_version_path: Option<String>, // Same for versions, under the same packages as the names
+    pub json_description_path: Option<String>, // Same for descriptions
+    pub detect_path: Option<String>,   // Path whose existence means the manager is present
+    pub detect_binary: Option<String>, // Binary on PATH whose presence means the manager is present
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Lines,
+    Json,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,14 +76,95 @@ struct PackageManagerToml {
     package_manager: PackageManagerConfig,
 }
 
+#[derive(Clone)]
 pub struct PackageManagerRegistry {
     pub managers: HashMap<String, PackageManagerConfig>,
+    availability: HashMap<String, bool>, // Cached executable lookups, keyed by manager name
+    pub load_errors: Vec<(String, String)>, // pkgmanagers/ files that couldn't be read or parsed, and why
+}
+
+// Split a configured command line into the program and its arguments, honouring
+// '...' and "..." quoting and backslash escapes the way a POSIX shell would
+pub fn split_command(cmd: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = cmd.chars();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    current.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(escaped @ ('"' | '\\' | '$' | '`')) => current.push(escaped),
+                            Some(other) => {
+                                current.push('\\');
+                                current.push(other);
+                            }
+                            None => current.push('\\'),
+                        },
+                        _ => current.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            _ => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    
+    if in_word {
+        words.push(current);
+    }
+    
+    let mut words = words.into_iter();
+    let program = words.next().unwrap_or_default();
+    (program, words.collect())
+}
+
+// Just the file name of a definition, for showing alongside its load error
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+// In-process PATH lookup, so availability checks don't spawn `which`
+pub fn binary_in_path(binary: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+        .unwrap_or(false)
 }
 
 impl PackageManagerRegistry {
     pub fn new() -> Self {
         Self {
             managers: HashMap::new(),
+            availability: HashMap::new(),
+            load_errors: Vec::new(),
         }
     }
     
@@ -48,10 +172,10 @@ impl PackageManagerRegistry {
         let mut registry = Self::new();
         let pm_dir = config_dir.join("pkgmanagers");
         
-        eprintln!("DEBUG: Looking for configs in: {:?}", pm_dir);
+        log::debug!("Looking for configs in: {:?}", pm_dir);
         
         if !pm_dir.exists() {
-            eprintln!("DEBUG: Config dir doesn't exist, creating and copying defaults");
+            log::debug!("Config dir doesn't exist, creating and copying defaults");
             std::fs::create_dir_all(&pm_dir)?;
             
             // Create default configs for common package managers
@@ -61,7 +185,7 @@ impl PackageManagerRegistry {
             let emerge_config = pm_dir.join("emerge.toml");
             let nix_config = pm_dir.join("nix.toml");
             
-            eprintln!("DEBUG: Force recreating emerge and nix configs");
+            log::debug!("Force recreating emerge and nix configs");
             // Remove configs
             let _ = std::fs::remove_file(&emerge_config);
             let _ = std::fs::remove_file(&nix_config);
@@ -75,6 +199,9 @@ list_packages_cmd = "equery list --portage-tree '*'"
 list_installed_cmd = "equery list '*'"
 search_cmd = "emerge --search {}"
 install_cmd = "emerge {}"
+preview_cmd = "emerge --pretend {}"
+remove_cmd = "emerge --depclean {}"
+version_install_format = "={name}-{version}"
 requires_root = true
 package_separator = " "
 installed_indicator = "✓"
@@ -88,6 +215,7 @@ list_packages_cmd = "nix-env -qaP --json"
 list_installed_cmd = "nix profile list"
 search_cmd = "nix search nixpkgs {}"
 install_cmd = "nix-env -iA nixpkgs.{}"
+remove_cmd = "nix-env -e {}"
 requires_root = false
 package_separator = " "
 installed_indicator = "*"
@@ -109,6 +237,7 @@ list_packages_cmd = "example-pm list-all"
 list_installed_cmd = "example-pm list-installed"
 search_cmd = "example-pm search {}"
 install_cmd = "example-pm install {}"
+remove_cmd = "example-pm remove {}"
 requires_root = false
 package_separator = " "
 installed_indicator = "*"
@@ -122,41 +251,59 @@ installed_indicator = "*"
 - `list_installed_cmd`: Command to list installed packages
 - `search_cmd`: Command to search packages (use {} as placeholder)
 - `install_cmd`: Command to install packages (use {} as placeholder)
+- `preview_cmd` (optional): Dry run of `install_cmd` whose output shows what would be installed, run before confirming an install (use {} as placeholder, e.g. "apt-get -s install {}")
+- `remove_cmd` (optional): Command to remove packages, used to undo pmux's last install (use {} as placeholder)
+- `version_install_format` (optional): How to write one package pinned to a version in `install_cmd`, using {name} and {version} (e.g. "{name}={version}")
+- `downgrade_cmd` (optional): Command to go back to an older version of an installed package; {} is the package written with `version_install_format` (e.g. "dnf downgrade {}")
+- `orphans_cmd` (optional): Command printing the names of installed packages that were pulled in as dependencies and are no longer needed, one per line (e.g. "pacman -Qdtq"). `O` in the Installed pane lists only these
 - `requires_root`: Whether installation needs sudo/root
 - `package_separator`: How to separate multiple package names
 - `installed_indicator`: Symbol to show for installed packages
+- `installed_indicator_is_regex` (optional): Treat `installed_indicator` as a regex matched against each listed line (e.g. "^\\[installed\\]"), for markers that only count in one position
+- `name_field` / `version_field` (optional): Which field of each line (counting from 0) holds the name and version, e.g. `name_field = 0` and `version_field = 2`. Used instead of `version_regex`; the name defaults to field 0
+- `field_separator` (optional): What separates the fields, e.g. "\t" or "|"; fields are trimmed. Runs of whitespace if unset
+- `output_format` (optional): `lines` (default) or `json` for list commands that print JSON
+- `json_name_path` (optional): With `json` output, where package names are, e.g. `packages[*].name`. `[*]` (or `*`) goes through every entry of an array or object; defaults to `[*].name`
+- `json_version_path` / `json_description_path` (optional): Where versions and descriptions are, under the same packages as the names, e.g. `packages[*].version`
+- `detect_path` (optional): Path whose existence means this manager is present
+- `detect_binary` (optional): Binary on PATH whose presence means this manager is present
 "#;
             std::fs::write(pm_dir.join("README.md"), readme_content)?;
         }
         
         // Load all .toml files from pkgmanagers directory
-        eprintln!("DEBUG: Loading configs from directory");
+        log::debug!("Loading configs from directory");
         for entry in std::fs::read_dir(&pm_dir)? {
             let entry = entry?;
             let path = entry.path();
-            eprintln!("DEBUG: Found file: {:?}", path);
+            log::debug!("Found file: {:?}", path);
             
             if path.extension().and_then(|s| s.to_str()) == Some("toml") {
                 match std::fs::read_to_string(&path) {
                     Ok(content) => {
-                        eprintln!("DEBUG: Read config file: {:?}", path.file_name());
+                        log::debug!("Read config file: {:?}", path.file_name());
                         match toml::from_str::<PackageManagerToml>(&content) {
                             Ok(toml_config) => {
                                 let config = toml_config.package_manager;
-                                eprintln!("DEBUG: Loaded package manager: {} with installed cmd: '{}'", config.name, config.list_installed_cmd);
+                                log::debug!("Loaded package manager: {} with installed cmd: '{}'", config.name, config.list_installed_cmd);
                                 registry.managers.insert(config.name.clone(), config);
                             }
                             Err(e) => {
-                                eprintln!("DEBUG: Failed to parse TOML in {:?}: {}", path, e);
+                                log::warn!("Failed to parse TOML in {:?}: {}", path, e);
+                                registry.load_errors.push((file_name(&path), e.to_string()));
                             }
                         }
                     }
                     Err(e) => {
-                        eprintln!("DEBUG: Failed to read file {:?}: {}", path, e);
+                        log::warn!("Failed to read file {:?}: {}", path, e);
+                        registry.load_errors.push((file_name(&path), e.to_string()));
                     }
                 }
             }
         }
+        registry.load_errors.sort();
+        
+        registry.refresh_availability();
         
         Ok(registry)
     }
@@ -164,23 +311,23 @@ installed_indicator = "*"
     fn create_default_configs(pm_dir: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
         // Try to copy from examples directory first
         let examples_dir = std::path::Path::new("examples");
-        eprintln!("DEBUG: Looking for examples in: {:?}", examples_dir);
+        log::debug!("Looking for examples in: {:?}", examples_dir);
         if examples_dir.exists() {
-            eprintln!("DEBUG: Examples directory found, copying configs");
+            log::debug!("Examples directory found, copying configs");
             for entry in std::fs::read_dir(examples_dir)? {
                 let entry = entry?;
                 let path = entry.path();
                 if path.extension().map_or(false, |ext| ext == "toml") {
                     if let Some(filename) = path.file_name() {
                         let dest = pm_dir.join(filename);
-                        eprintln!("DEBUG: Copying {:?} to {:?}", path, dest);
+                        log::debug!("Copying {:?} to {:?}", path, dest);
                         std::fs::copy(&path, &dest)?;
                     }
                 }
             }
             return Ok(());
         } else {
-            eprintln!("DEBUG: Examples directory not found, using hardcoded configs");
+            log::debug!("Examples directory not found, using hardcoded configs");
         }
         
         // Fallback to hardcoded configs if examples don't exist
@@ -194,6 +341,7 @@ list_packages_cmd = "nix-env -qaP --json"
 list_installed_cmd = "nix profile list"
 search_cmd = "nix search nixpkgs {}"
 install_cmd = "nix-env -iA nixpkgs.{}"
+remove_cmd = "nix-env -e {}"
 requires_root = false
 package_separator = " "
 installed_indicator = "*"
@@ -209,6 +357,7 @@ list_packages_cmd = "paru -Slqa"
 list_installed_cmd = "paru -Q"
 search_cmd = "paru -Ss {}"
 install_cmd = "paru -S {}"
+remove_cmd = "paru -R {}"
 requires_root = false
 package_separator = " "
 installed_indicator = "*"
@@ -224,6 +373,11 @@ list_packages_cmd = "apt list"
 list_installed_cmd = "apt list --installed"
 search_cmd = "apt search {}"
 install_cmd = "apt install {}"
+preview_cmd = "apt-get -s install {}"
+remove_cmd = "apt remove {}"
+version_install_format = "{name}={version}"
+downgrade_cmd = "apt install --allow-downgrades {}"
+orphans_cmd = "apt-get -s autoremove | sed -n 's/^Remv \\([^ ]*\\).*/\\1/p'"
 requires_root = true
 package_separator = " "
 installed_indicator = "*"
@@ -239,6 +393,9 @@ list_packages_cmd = "equery list --portage-tree '*'"
 list_installed_cmd = "equery list '*'"
 search_cmd = "emerge --search {}"
 install_cmd = "emerge {}"
+preview_cmd = "emerge --pretend {}"
+remove_cmd = "emerge --depclean {}"
+version_install_format = "={name}-{version}"
 requires_root = true
 package_separator = " "
 installed_indicator = "✓"
@@ -254,6 +411,11 @@ list_packages_cmd = "dnf list --available"
 list_installed_cmd = "dnf list --installed"
 search_cmd = "dnf search {}"
 install_cmd = "dnf install {}"
+preview_cmd = "dnf install --assumeno {}"
+remove_cmd = "dnf remove {}"
+version_install_format = "{name}-{version}"
+downgrade_cmd = "dnf downgrade {}"
+orphans_cmd = "dnf repoquery --unneeded --queryformat '%{name}\\n'"
 requires_root = true
 package_separator = " "
 installed_indicator = "@"
@@ -269,12 +431,62 @@ list_packages_cmd = "pacman -Slq"
 list_installed_cmd = "pacman -Q"
 search_cmd = "pacman -Ss {}"
 install_cmd = "pacman -S {}"
+remove_cmd = "pacman -R {}"
+orphans_cmd = "pacman -Qdtq"
 requires_root = true
 package_separator = " "
 installed_indicator = "*"
 "#;
         std::fs::write(pm_dir.join("pacman.toml"), pacman_config)?;
         
+        // pkg config (FreeBSD)
+        let pkg_config = r#"[package_manager]
+name = "pkg"
+display_name = "pkg (FreeBSD)"
+executable = "pkg"
+list_packages_cmd = "pkg search -g '*'"
+list_installed_cmd = "pkg info"
+search_cmd = "pkg search {}"
+install_cmd = "pkg install {}"
+remove_cmd = "pkg delete {}"
+requires_root = true
+package_separator = " "
+installed_indicator = "*"
+"#;
+        std::fs::write(pm_dir.join("pkg.toml"), pkg_config)?;
+        
+        // opkg config (OpenWrt)
+        let opkg_config = r#"[package_manager]
+name = "opkg"
+display_name = "opkg (OpenWrt)"
+executable = "opkg"
+list_packages_cmd = "opkg list"
+list_installed_cmd = "opkg list-installed"
+search_cmd = "opkg find '*{}*'"
+install_cmd = "opkg install {}"
+remove_cmd = "opkg remove {}"
+requires_root = false
+package_separator = " "
+installed_indicator = "*"
+"#;
+        std::fs::write(pm_dir.join("opkg.toml"), opkg_config)?;
+        
+        // pkgin config (NetBSD/pkgsrc)
+        let pkgin_config = r#"[package_manager]
+name = "pkgin"
+display_name = "pkgin (pkgsrc)"
+executable = "pkgin"
+list_packages_cmd = "pkgin avail"
+list_installed_cmd = "pkgin list"
+search_cmd = "pkgin search {}"
+install_cmd = "pkgin install {}"
+remove_cmd = "pkgin remove {}"
+requires_root = true
+package_separator = " "
+installed_indicator = "="
+"#;
+        std::fs::write(pm_dir.join("pkgin.toml"), pkgin_config)?;
+        
         Ok(())
     }
     
@@ -288,21 +500,38 @@ installed_indicator = "*"
             .collect()
     }
     
-    // Removed list_packages - now using local detection and APIs
+    // Built-in managers are listed through local detection and APIs; these run the
+    // configured commands for managers that only exist as a TOML definition
+    pub fn list_packages(&self, manager: &PackageManagerConfig) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        self.run_list_command(manager, &manager.list_packages_cmd, None)
+    }
     
-    // Removed list_installed - now using local detection
+    pub fn list_installed(&self, manager: &PackageManagerConfig) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        self.run_list_command(manager, &manager.list_installed_cmd, None)
+    }
     
     pub fn search(&self, manager: &PackageManagerConfig, query: &str) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        self.run_list_command(manager, &manager.search_cmd, Some(query))
+    }
+    
+    fn run_list_command(&self, manager: &PackageManagerConfig, cmd: &str, query: Option<&str>) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
         if !self.is_available(manager) {
             return Ok(vec![]);
         }
         
-        let cmd = manager.search_cmd.replace("{}", query);
-        let args: Vec<&str> = cmd.split_whitespace().collect();
+        let (program, mut args) = split_command(cmd);
+        if program.is_empty() {
+            return Ok(vec![]);
+        }
         
-        let output = Command::new(args[0])
-            .args(&args[1..])
-            .output()?;
+        // Substitute after splitting so a query with spaces stays a single argument
+        if let Some(query) = query {
+            for arg in &mut args {
+                *arg = arg.replace("{}", query);
+            }
+        }
+        
+        let output = crate::core::process::output_with_timeout(Command::new(&program).args(&args))?;
             
         if !output.status.success() {
             return Ok(vec![]);
@@ -312,60 +541,90 @@ installed_indicator = "*"
         self.parse_package_list(&stdout, manager)
     }
     
-    pub fn get_install_command(&self, manager: &PackageManagerConfig, packages: &[String]) -> String {
+    pub fn get_install_command(&self, manager: &PackageManagerConfig, packages: &[String], sudoers: &str) -> String {
         let package_list = packages.join(&manager.package_separator);
         let cmd = manager.install_cmd.replace("{}", &package_list);
         
         if manager.requires_root {
-            format!("sudo {}", cmd)
+            format!("{} {}", sudoers, cmd)
         } else {
             cmd
         }
     }
     
-    fn is_available(&self, manager: &PackageManagerConfig) -> bool {
-        let available = Command::new("which")
-            .arg(&manager.executable)
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false);
+    pub fn get_remove_command(&self, manager: &PackageManagerConfig, packages: &[String], sudoers: &str) -> Option<String> {
+        let package_list = packages.join(&manager.package_separator);
+        let cmd = manager.remove_cmd.as_ref()?.replace("{}", &package_list);
+        
+        if manager.requires_root {
+            Some(format!("{} {}", sudoers, cmd))
+        } else {
+            Some(cmd)
+        }
+    }
+    
+    // `spec` is one package already written with the manager's version_install_format
+    pub fn get_downgrade_command(&self, manager: &PackageManagerConfig, spec: &str, sudoers: &str) -> Option<String> {
+        let cmd = manager.downgrade_cmd.as_ref()?.replace("{}", spec);
+        
+        if manager.requires_root {
+            Some(format!("{} {}", sudoers, cmd))
+        } else {
+            Some(cmd)
+        }
+    }
+    
+    // Availability is resolved once per registry load; managers added afterwards are checked on demand
+    pub fn is_available(&self, manager: &PackageManagerConfig) -> bool {
+        self.availability
+            .get(&manager.name)
+            .copied()
+            .unwrap_or_else(|| Self::check_available(manager))
+    }
+    
+    pub fn refresh_availability(&mut self) {
+        self.availability = self.managers
+            .iter()
+            .map(|(name, manager)| (name.clone(), Self::check_available(manager)))
+            .collect();
+    }
+    
+    fn check_available(manager: &PackageManagerConfig) -> bool {
+        let available = binary_in_path(&manager.executable);
         
         // Special case for emerge: also check if equery is available since we use it for listing
-        let final_available = if manager.name == "emerge" {
-            let equery_available = Command::new("which")
-                .arg("equery")
-                .output()
-                .map(|output| output.status.success())
-                .unwrap_or(false);
-            
-            eprintln!("DEBUG: {} executable '{}' available: {}, equery available: {}", 
+        if manager.name == "emerge" {
+            let equery_available = binary_in_path("equery");
+            log::debug!("{} executable '{}' available: {}, equery available: {}", 
                      manager.name, manager.executable, available, equery_available);
             available && equery_available
         } else {
-            eprintln!("DEBUG: {} executable '{}' available: {}", manager.name, manager.executable, available);
+            log::debug!("{} executable '{}' available: {}", manager.name, manager.executable, available);
             available
-        };
-        
-        final_available
+        }
     }
     
     fn parse_package_list(&self, output: &str, manager: &PackageManagerConfig) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
         let mut packages = Vec::new();
         
-        eprintln!("DEBUG: parse_package_list called for {}", manager.name);
-        eprintln!("DEBUG: Output has {} lines", output.lines().count());
+        log::debug!("parse_package_list called for {}", manager.name);
+        log::debug!("Output has {} lines", output.lines().count());
+        
+        if manager.output_format == OutputFormat::Json {
+            return self.parse_json_package_list(output, manager);
+        }
         
         match manager.name.as_str() {
             "nix" => {
-                eprintln!("DEBUG: Using nix parser");
+                log::debug!("Using nix parser");
                 // Handle different nix command outputs
                 if output.trim().starts_with('{') {
-                    eprintln!("DEBUG: Detected JSON output from nix");
+                    log::debug!("Detected JSON output from nix");
                     // Handle nix-env -qaP --json output
                     match serde_json::from_str::<serde_json::Value>(output) {
                         Ok(json_data) => {
                             if let Some(obj) = json_data.as_object() {
-                                eprintln!("DEBUG: JSON has {} entries", obj.len());
+                                log::debug!("JSON has {} entries", obj.len());
                                 for (key, value) in obj {
                                     if let Some(pkg_obj) = value.as_object() {
                                         // Use pname if available, otherwise fall back to name or key
@@ -386,21 +645,21 @@ installed_indicator = "*"
                                             name: package_name,
                                             version,
                                             description,
-                                            installed: false,
                                             source: manager.name.clone(),
+                                            ..Default::default()
                                         };
                                         packages.push(package);
                                     }
                                 }
-                                eprintln!("DEBUG: Parsed {} packages from JSON", packages.len());
+                                log::debug!("Parsed {} packages from JSON", packages.len());
                             }
                         }
                         Err(e) => {
-                            eprintln!("DEBUG: Failed to parse JSON: {}", e);
+                            log::warn!("Failed to parse JSON: {}", e);
                         }
                     }
                 } else {
-                    eprintln!("DEBUG: Detected text output from nix");
+                    log::debug!("Detected text output from nix");
                     let mut line_count = 0;
                     let mut parsed_count = 0;
                     
@@ -417,19 +676,18 @@ installed_indicator = "*"
                             if let Some(name) = line.strip_prefix("Name:").map(|s| s.trim()) {
                                 let package = Package {
                                     name: name.to_string(),
-                                    version: None,
-                                    description: None,
                                     installed: true,
                                     source: manager.name.clone(),
+                                    ..Default::default()
                                 };
                                 packages.push(package);
                                 parsed_count += 1;
-                                eprintln!("DEBUG: Found installed package: '{}'", name);
+                                log::debug!("Found installed package: '{}'", name);
                             }
                         }
                     }
                     
-                    eprintln!("DEBUG: Nix parser processed {} lines, parsed {} packages", line_count, parsed_count);
+                    log::debug!("Nix parser processed {} lines, parsed {} packages", line_count, parsed_count);
                 }
             }
             "paru" => {
@@ -445,8 +703,8 @@ installed_indicator = "*"
                             name: name.to_string(),
                             version: parts.get(1).map(|v| v.to_string()),
                             description: parts.get(2..).map(|d| d.join(" ")),
-                            installed: false,
                             source: manager.name.clone(),
+                            ..Default::default()
                         };
                         packages.push(package);
                     }
@@ -468,13 +726,14 @@ installed_indicator = "*"
                             description: parts.get(3..).map(|d| d.join(" ")),
                             installed: line.contains("[installed"),
                             source: manager.name.clone(),
+                            ..Default::default()
                         };
                         packages.push(package);
                     }
                 }
             }
             "emerge" => {
-                eprintln!("DEBUG: Using emerge parser");
+                log::debug!("Using emerge parser");
                 // Handle emerge/equery output
                 // Format: [-P-] [  ] acct-group/3proxy-0:0
                 // Format: [IP-] [  ] acct-group/audio-0-r3:0
@@ -485,24 +744,24 @@ installed_indicator = "*"
                     line_count += 1;
                     
                     if line.trim().is_empty() || !line.starts_with('[') {
-                        eprintln!("DEBUG: Skipping line {}: '{}'", line_count, line);
+                        log::debug!("Skipping line {}: '{}'", line_count, line);
                         continue;
                     }
                     
                     // Skip the "* Searching for * ..." line
                     if line.contains("Searching for") {
-                        eprintln!("DEBUG: Skipping search header: '{}'", line);
+                        log::debug!("Skipping search header: '{}'", line);
                         continue;
                     }
                     
-                    eprintln!("DEBUG: Processing emerge line {}: '{}'", line_count, line);
+                    log::debug!("Processing emerge line {}: '{}'", line_count, line);
                     
                     // Parse the status flags [I--] or [-P-]
                     let installed = line.starts_with("[I");
                     
                     // Simple approach: split by spaces and get the 3rd element (index 2)
                     let parts: Vec<&str> = line.split_whitespace().collect();
-                    eprintln!("DEBUG: Split into {} parts: {:?}", parts.len(), parts);
+                    log::debug!("Split into {} parts: {:?}", parts.len(), parts);
                     
                     if parts.len() >= 3 {
                         let atom_full = parts[2]; // The package atom
@@ -510,25 +769,36 @@ installed_indicator = "*"
                         // Split by : to remove slot
                         let atom = atom_full.split(':').next().unwrap_or(atom_full);
                         
-                        eprintln!("DEBUG: Extracted atom: '{}' (installed: {})", atom, installed);
+                        log::debug!("Extracted atom: '{}' (installed: {})", atom, installed);
                         
                         let package = Package {
                             name: atom.to_string(),
-                            version: None, // Version is embedded in atom
-                            description: None,
                             installed,
                             source: manager.name.clone(),
+                            ..Default::default()
                         };
                         packages.push(package);
                         parsed_count += 1;
                     } else {
-                        eprintln!("DEBUG: Not enough parts in line: '{}'", line);
+                        log::debug!("Not enough parts in line: '{}'", line);
                     }
                 }
                 
-                eprintln!("DEBUG: Emerge parser processed {} lines, parsed {} packages", line_count, parsed_count);
+                log::debug!("Emerge parser processed {} lines, parsed {} packages", line_count, parsed_count);
             }
             _ => {
+                // A regex indicator is compiled once; a bad pattern marks nothing installed
+                let indicator_regex = match &manager.installed_indicator {
+                    Some(pattern) if manager.installed_indicator_is_regex => match Regex::new(pattern) {
+                        Ok(regex) => Some(regex),
+                        Err(e) => {
+                            log::warn!("Invalid installed_indicator regex for {}: {}", manager.name, e);
+                            None
+                        }
+                    },
+                    _ => None,
+                };
+                
                 // Generic parsing with optional regex cleanup
                 for line in output.lines() {
                     if line.trim().is_empty() {
@@ -548,8 +818,20 @@ installed_indicator = "*"
                         }
                     }
                     
-                    // Extract name and version using version regex
-                    let (name, version) = if let Some(version_pattern) = &manager.version_regex {
+                    // Extract name and version from configured fields, else using version regex
+                    let (name, version) = if manager.name_field.is_some() || manager.version_field.is_some() {
+                        let fields: Vec<&str> = match &manager.field_separator {
+                            Some(separator) if !separator.is_empty() => processed_line.split(separator.as_str()).map(str::trim).collect(),
+                            _ => processed_line.split_whitespace().collect(),
+                        };
+                        let field = |index: usize| fields.get(index).copied().filter(|f| !f.is_empty());
+                        
+                        // Short lines (headers, footers, wrapped text) have no name to read
+                        let Some(name) = field(manager.name_field.unwrap_or(0)) else {
+                            continue;
+                        };
+                        (name.to_string(), manager.version_field.and_then(field).map(str::to_string))
+                    } else if let Some(version_pattern) = &manager.version_regex {
                         if let Ok(regex) = Regex::new(version_pattern) {
                             if let Some(captures) = regex.captures(processed_line) {
                                 let pkg_name = captures.get(1).map(|m| m.as_str()).unwrap_or(processed_line);
@@ -570,7 +852,9 @@ installed_indicator = "*"
                     };
                     
                     // Check if installed using indicator
-                    let installed = if let Some(indicator) = &manager.installed_indicator {
+                    let installed = if manager.installed_indicator_is_regex {
+                        indicator_regex.as_ref().is_some_and(|regex| regex.is_match(line))
+                    } else if let Some(indicator) = &manager.installed_indicator {
                         line.contains(indicator)
                     } else {
                         false
@@ -579,9 +863,9 @@ installed_indicator = "*"
                     let package = Package {
                         name,
                         version,
-                        description: None, // Generic parser doesn't extract descriptions
                         installed,
                         source: manager.name.clone(),
+                        ..Default::default()
                     };
                     packages.push(package);
                 }
@@ -590,4 +874,256 @@ installed_indicator = "*"
         
         Ok(packages)
     }
+    
+    // Each package is found through json_name_path: everything up to its last wildcard selects
+    // the packages, and the rest (and the rest of the other paths) is read from each one
+    fn parse_json_package_list(&self, output: &str, manager: &PackageManagerConfig) -> Result<Vec<Package>, Box<dyn std::error::Error>> {
+        let json: serde_json::Value = serde_json::from_str(output)?;
+        let name_path = parse_json_path(manager.json_name_path.as_deref().unwrap_or("[*].name"));
+        let split = name_path.iter().rposition(|segment| segment == "*").map_or(0, |i| i + 1);
+        let (items_path, name_rest) = name_path.split_at(split);
+        
+        let relative = |path: &Option<String>| -> Option<Vec<String>> {
+            let segments = parse_json_path(path.as_deref()?);
+            let rest = segments.strip_prefix(items_path).map(<[String]>::to_vec);
+            if rest.is_none() {
+                log::warn!("{}: {:?} is not under the packages selected by json_name_path", manager.name, path);
+            }
+            rest
+        };
+        let version_rest = relative(&manager.json_version_path);
+        let description_rest = relative(&manager.json_description_path);
+        let text_at = |item: &serde_json::Value, rest: &Option<Vec<String>>| {
+            rest.as_ref().and_then(|rest| select_json(item, rest).first().and_then(|value| json_text(value)))
+        };
+        
+        let packages = select_json(&json, items_path)
+            .into_iter()
+            .filter_map(|item| {
+                let name = select_json(item, name_rest).first().and_then(|value| json_text(value))?;
+                Some(Package {
+                    name,
+                    version: text_at(item, &version_rest),
+                    description: text_at(item, &description_rest),
+                    source: manager.name.clone(),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        
+        Ok(packages)
+    }
+}
+
+// "$.packages[*].name" -> ["packages", "*", "name"]; the leading "$" is optional
+fn parse_json_path(path: &str) -> Vec<String> {
+    path.trim()
+        .trim_start_matches('$')
+        .replace('[', ".")
+        .replace(']', "")
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Every value at `path`; "*" fans out over an array's elements or an object's values, and a
+// number indexes into an array
+fn select_json<'a>(value: &'a serde_json::Value, path: &[String]) -> Vec<&'a serde_json::Value> {
+    let Some((segment, rest)) = path.split_first() else {
+        return vec![value];
+    };
+    
+    match (segment.as_str(), value) {
+        ("*", serde_json::Value::Array(items)) => items.iter().flat_map(|item| select_json(item, rest)).collect(),
+        ("*", serde_json::Value::Object(map)) => map.values().flat_map(|item| select_json(item, rest)).collect(),
+        (index, serde_json::Value::Array(items)) => index
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| items.get(index))
+            .map_or_else(Vec::new, |item| select_json(item, rest)),
+        (key, serde_json::Value::Object(map)) => map.get(key).map_or_else(Vec::new, |item| select_json(item, rest)),
+        _ => Vec::new(),
+    }
+}
+
+fn json_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Number(number) => Some(number.to_string()),
+        serde_json::Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_command_plain_words() {
+        let (program, args) = split_command("pacman -Ss  firefox");
+        assert_eq!(program, "pacman");
+        assert_eq!(args, vec!["-Ss", "firefox"]);
+    }
+
+    #[test]
+    fn split_command_single_quotes() {
+        let (program, args) = split_command("equery list --portage-tree '*'");
+        assert_eq!(program, "equery");
+        assert_eq!(args, vec!["list", "--portage-tree", "*"]);
+    }
+
+    #[test]
+    fn split_command_double_quotes_with_spaces_and_escapes() {
+        let (program, args) = split_command(r#"rpm -qa --queryformat "%{NAME} \"%{VERSION}\"\n""#);
+        assert_eq!(program, "rpm");
+        assert_eq!(args, vec!["-qa", "--queryformat", r#"%{NAME} "%{VERSION}"\n"#]);
+    }
+
+    #[test]
+    fn split_command_empty_quoted_argument() {
+        let (program, args) = split_command("xbps-query -Rs ''");
+        assert_eq!(program, "xbps-query");
+        assert_eq!(args, vec!["-Rs", ""]);
+    }
+
+    #[test]
+    fn split_command_adjacent_quotes_join_into_one_word() {
+        let (_, args) = split_command(r#"tool --name='a b'"c d" plain\ word"#);
+        assert_eq!(args, vec!["--name=a bc d", "plain word"]);
+    }
+
+    #[test]
+    fn split_command_empty_input() {
+        let (program, args) = split_command("   ");
+        assert!(program.is_empty());
+        assert!(args.is_empty());
+    }
+
+    fn configured_manager(extra: &str) -> PackageManagerConfig {
+        let definition = format!(
+            r#"name = "example"
+display_name = "Example"
+executable = "example"
+list_packages_cmd = "example list --json"
+list_installed_cmd = "example list --installed --json"
+search_cmd = "example search {{}}"
+install_cmd = "example install {{}}"
+requires_root = false
+package_separator = " "
+{}"#,
+            extra
+        );
+        toml::from_str(&definition).unwrap()
+    }
+
+    #[test]
+    fn parse_json_package_list_reads_nested_arrays() {
+        let manager = configured_manager(
+            r#"output_format = "json"
+json_name_path = "$.packages[*].name"
+json_version_path = "$.packages[*].version.full""#,
+        );
+        let output = r#"{"packages": [
+            {"name": "ripgrep", "version": {"full": "14.1.0"}},
+            {"name": "fd"},
+            {"version": {"full": "1.0"}}
+        ]}"#;
+
+        let packages = PackageManagerRegistry::new().parse_package_list(output, &manager).unwrap();
+        let found: Vec<(&str, Option<&str>)> = packages.iter().map(|p| (p.name.as_str(), p.version.as_deref())).collect();
+        assert_eq!(found, vec![("ripgrep", Some("14.1.0")), ("fd", None)]);
+        assert!(packages.iter().all(|p| p.source == "example"));
+    }
+
+    #[test]
+    fn parse_json_package_list_walks_object_values() {
+        // nix-env -qaP --json style: an object keyed by attribute path
+        let manager = configured_manager(
+            r#"output_format = "json"
+json_name_path = "*.pname"
+json_version_path = "*.version""#,
+        );
+        let output = r#"{"nixpkgs.hello": {"pname": "hello", "version": "2.12.1"}}"#;
+
+        let packages = PackageManagerRegistry::new().parse_package_list(output, &manager).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "hello");
+        assert_eq!(packages[0].version.as_deref(), Some("2.12.1"));
+    }
+
+    #[test]
+    fn parse_package_list_reads_configured_fields() {
+        let manager = configured_manager(
+            r#"name_field = 1
+version_field = 3
+field_separator = "|""#,
+        );
+        let output = " ii | curl | amd64 | 8.5.0\n ii | libfoo | amd64 |\n--\n";
+
+        let packages = PackageManagerRegistry::new().parse_package_list(output, &manager).unwrap();
+        let found: Vec<(&str, Option<&str>)> = packages.iter().map(|p| (p.name.as_str(), p.version.as_deref())).collect();
+        assert_eq!(found, vec![("curl", Some("8.5.0")), ("libfoo", None)]);
+    }
+
+    #[test]
+    fn parse_package_list_splits_fields_on_whitespace_by_default() {
+        let manager = configured_manager("version_field = 2");
+        let output = "ripgrep  x86_64   14.1.0\nfd\n";
+
+        let packages = PackageManagerRegistry::new().parse_package_list(output, &manager).unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "ripgrep");
+        assert_eq!(packages[0].version.as_deref(), Some("14.1.0"));
+        assert_eq!(packages[1].name, "fd");
+        assert_eq!(packages[1].version, None);
+    }
+
+    #[test]
+    fn installed_indicator_is_regex_only_matches_where_anchored() {
+        let output = "[*] vim-9.0_1      Vim editor\n[-] nano-7.2_1     Pico clone, not [*] installed\n";
+        let installed = |extra: &str| -> Vec<(String, bool)> {
+            let manager = configured_manager(&format!("name_field = 1\n{}", extra));
+            PackageManagerRegistry::new()
+                .parse_package_list(output, &manager)
+                .unwrap()
+                .into_iter()
+                .map(|p| (p.name, p.installed))
+                .collect()
+        };
+
+        assert_eq!(
+            installed("installed_indicator = '^\\[\\*\\]'\ninstalled_indicator_is_regex = true"),
+            vec![("vim-9.0_1".to_string(), true), ("nano-7.2_1".to_string(), false)]
+        );
+        // As a plain substring the marker in the description counts too
+        assert_eq!(
+            installed("installed_indicator = '[*]'"),
+            vec![("vim-9.0_1".to_string(), true), ("nano-7.2_1".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn load_from_config_dir_records_broken_definitions() {
+        let config_dir = std::env::temp_dir().join(format!("pmux-load-errors-{}", std::process::id()));
+        let pm_dir = config_dir.join("pkgmanagers");
+        std::fs::create_dir_all(&pm_dir).unwrap();
+        std::fs::write(
+            pm_dir.join("example.toml"),
+            toml::to_string(&PackageManagerToml { package_manager: configured_manager("") }).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(pm_dir.join("broken.toml"), "[package_manager]\nname = \"broken\"\n").unwrap();
+
+        let registry = PackageManagerRegistry::load_from_config_dir(&config_dir).unwrap();
+        std::fs::remove_dir_all(&config_dir).unwrap();
+
+        assert!(registry.get_manager("example").is_some());
+        assert!(registry.get_manager("broken").is_none());
+        assert_eq!(registry.load_errors.len(), 1);
+        assert_eq!(registry.load_errors[0].0, "broken.toml");
+        assert!(registry.load_errors[0].1.contains("missing field"));
+    }
 }
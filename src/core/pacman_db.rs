@@ -0,0 +1,201 @@
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use xz2::read::XzDecoder;
+
+// Fields of a pacman `desc` file, keyed by section name without the `%` markers
+// (e.g. "NAME", "VERSION", "DESC"). Each section may hold several lines.
+pub type DescFields = HashMap<String, Vec<String>>;
+
+pub fn parse_desc(content: &str) -> DescFields {
+    let mut fields = DescFields::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.trim_end();
+
+        if line.len() > 2 && line.starts_with('%') && line.ends_with('%') {
+            let key = line.trim_matches('%').to_string();
+            fields.entry(key.clone()).or_default();
+            current = Some(key);
+        } else if line.is_empty() {
+            current = None;
+        } else if let Some(key) = &current {
+            if let Some(values) = fields.get_mut(key) {
+                values.push(line.to_string());
+            }
+        }
+    }
+
+    fields
+}
+
+pub fn first_value<'a>(fields: &'a DescFields, key: &str) -> Option<&'a str> {
+    fields.get(key)?.first().map(|s| s.as_str())
+}
+
+// A free-text section such as %DESC%, which may wrap over several lines, as one line
+pub fn joined_value(fields: &DescFields, key: &str) -> Option<String> {
+    let values = fields.get(key)?;
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.join(" "))
+}
+
+// Dependency entries carry optional version constraints ("glibc>=2.38") and
+// optdepends carry a reason ("python: for scripts"); keep just the package name
+pub fn strip_version_constraint(dependency: &str) -> &str {
+    let end = dependency.find(['<', '>', '=', ':']).unwrap_or(dependency.len());
+    dependency[..end].trim()
+}
+
+// Virtual names from %PROVIDES%, without their versions ("sh", "java-runtime")
+pub fn provides(fields: &DescFields) -> Vec<String> {
+    fields
+        .get("PROVIDES")
+        .map(|values| values.iter().map(|p| strip_version_constraint(p).to_string()).collect())
+        .unwrap_or_default()
+}
+
+// Names from %CONFLICTS%. Versioned conflicts ("foo<2.0") almost always target old
+// releases, and without comparing versions they'd flag current ones, so they're left out
+pub fn conflicts(fields: &DescFields) -> Vec<String> {
+    fields
+        .get("CONFLICTS")
+        .map(|values| values.iter().filter(|c| !c.contains(['<', '>', '='])).cloned().collect())
+        .unwrap_or_default()
+}
+
+// Read every package's `desc` entry from a sync database without shelling out to tar.
+// Sync databases are tar archives compressed with gzip, xz or zstd (or left uncompressed),
+// so the compression is sniffed from the magic bytes rather than the file extension.
+pub fn read_sync_db(path: &Path) -> Result<Vec<DescFields>, Box<dyn std::error::Error>> {
+    let mut file = File::open(path)?;
+    let mut magic = [0u8; 6];
+    let read = file.read(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let reader: Box<dyn Read> = match &magic[..read] {
+        [0x1f, 0x8b, ..] => Box::new(GzDecoder::new(BufReader::new(file))),
+        [0xfd, b'7', b'z', b'X', b'Z', 0x00] => Box::new(XzDecoder::new(BufReader::new(file))),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Box::new(zstd::Decoder::new(file)?),
+        _ => Box::new(BufReader::new(file)),
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let is_desc = entry.path()?.file_name().is_some_and(|name| name == "desc");
+        if !is_desc {
+            continue;
+        }
+
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        entries.push(parse_desc(&content));
+    }
+
+    Ok(entries)
+}
+
+// Where pacman keeps the packages it has downloaded, which can be reinstalled with `pacman -U`
+pub const PACKAGE_CACHE_DIR: &str = "/var/cache/pacman/pkg";
+
+// The "pkgver-pkgrel" of a cached package file such as "ripgrep-14.1.0-1-x86_64.pkg.tar.zst",
+// if the file is one of `name`'s. Another package whose name merely starts with `name`
+// ("ripgrep-all-...") leaves more than one dash before the architecture, so isn't matched
+pub fn cached_package_version<'a>(file_name: &'a str, name: &str) -> Option<&'a str> {
+    let rest = file_name.strip_prefix(name)?.strip_prefix('-')?;
+    let (rest, extension) = rest.split_once(".pkg.tar")?;
+    if extension.ends_with(".sig") {
+        return None;
+    }
+
+    let (version, _arch) = rest.rsplit_once('-')?;
+    let (pkgver, pkgrel) = version.split_once('-')?;
+    let valid = !pkgver.is_empty() && !pkgrel.is_empty() && pkgrel.bytes().all(|b| b.is_ascii_digit() || b == b'.');
+    valid.then_some(version)
+}
+
+// Every cached version of a package with its file, in no particular order
+pub fn cached_versions(cache_dir: &Path, name: &str) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(cache_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let version = cached_package_version(file_name.to_str()?, name)?.to_string();
+            Some((version, entry.path()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTI_LINE_DESC: &str = "%NAME%
+example
+
+%VERSION%
+1.2.3-1
+
+%DESC%
+An example package whose description
+wraps over two lines
+
+%DEPENDS%
+glibc>=2.38
+zlib
+
+%PROVIDES%
+libexample.so=1-64
+example-bin
+
+";
+
+    #[test]
+    fn parse_desc_keeps_every_line_of_a_section() {
+        let fields = parse_desc(MULTI_LINE_DESC);
+        assert_eq!(first_value(&fields, "NAME"), Some("example"));
+        assert_eq!(fields["DESC"], vec!["An example package whose description", "wraps over two lines"]);
+        assert_eq!(fields["DEPENDS"], vec!["glibc>=2.38", "zlib"]);
+    }
+
+    #[test]
+    fn joined_value_joins_a_multi_line_description() {
+        let fields = parse_desc(MULTI_LINE_DESC);
+        assert_eq!(
+            joined_value(&fields, "DESC").as_deref(),
+            Some("An example package whose description wraps over two lines")
+        );
+        assert_eq!(joined_value(&fields, "URL"), None);
+    }
+
+    #[test]
+    fn provides_strips_versions_from_every_line() {
+        let fields = parse_desc(MULTI_LINE_DESC);
+        assert_eq!(provides(&fields), vec!["libexample.so", "example-bin"]);
+    }
+
+    #[test]
+    fn cached_package_version_reads_the_file_name() {
+        assert_eq!(cached_package_version("ripgrep-14.1.0-1-x86_64.pkg.tar.zst", "ripgrep"), Some("14.1.0-1"));
+        assert_eq!(cached_package_version("python-1:3.12.3-1.1-x86_64.pkg.tar.xz", "python"), Some("1:3.12.3-1.1"));
+        assert_eq!(cached_package_version("ripgrep-14.1.0-1-x86_64.pkg.tar.zst.sig", "ripgrep"), None);
+    }
+
+    #[test]
+    fn cached_package_version_skips_packages_sharing_a_prefix() {
+        assert_eq!(cached_package_version("ripgrep-all-0.10.6-1-x86_64.pkg.tar.zst", "ripgrep"), None);
+        assert_eq!(cached_package_version("ripgrep-all-0.10.6-1-x86_64.pkg.tar.zst", "ripgrep-all"), Some("0.10.6-1"));
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::{self, Read};
+use std::process::{Command, Output, Stdio};
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// From `[pm] load_timeout_secs`, set before package loading starts and again when the
+// config is reloaded. Zero waits forever
+static LOAD_TIMEOUT: RwLock<Option<Duration>> = RwLock::new(None);
+
+pub fn set_load_timeout(timeout: Duration) {
+    if let Ok(mut load_timeout) = LOAD_TIMEOUT.write() {
+        *load_timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+    }
+}
+
+// Like `Command::output`, but kills the process if it outlives the load timeout so a
+// slow or hung listing command can't stall package loading forever
+pub fn output_with_timeout(command: &mut Command) -> io::Result<Output> {
+    let Some(timeout) = LOAD_TIMEOUT.read().ok().and_then(|timeout| *timeout) else {
+        return command.output();
+    };
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain both pipes while waiting, otherwise a chatty process blocks on a full pipe
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.and_then(|reader| reader.join().ok()).unwrap_or_default(),
+        stderr: stderr.and_then(|reader| reader.join().ok()).unwrap_or_default(),
+    })
+}
+
+fn read_in_background<R: Read + Send + 'static>(mut pipe: R) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = pipe.read_to_end(&mut buffer);
+        buffer
+    })
+}
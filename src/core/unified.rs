@@ -29,7 +29,7 @@ impl UnifiedPackageManager {
                     all_installed.extend(packages);
                 }
                 Err(e) => {
-                    eprintln!("Failed to load installed packages from {}: {}", manager.name, e);
+                    log::warn!("Failed to load installed packages from {}: {}", manager.name, e);
                 }
             }
         }
@@ -55,7 +55,7 @@ impl UnifiedPackageManager {
                             all_packages.extend(packages);
                         }
                         Err(e) => {
-                            eprintln!("Failed to search Arch packages: {}", e);
+                            log::warn!("Failed to search Arch packages: {}", e);
                         }
                     }
                 }
@@ -0,0 +1,228 @@
+use crate::core::package_managers::Package;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// Compare two version strings the way rpm and pacman do: an optional "epoch:" first,
+// then runs of digits compared numerically and runs of letters compared lexically.
+// A digit run beats a letter run ("1.0.1" > "1.0a") and "~" sorts before everything,
+// so "1.0~rc1" < "1.0"
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (epoch_a, rest_a) = split_epoch(a);
+    let (epoch_b, rest_b) = split_epoch(b);
+    epoch_a.cmp(&epoch_b).then_with(|| compare_segments(rest_a, rest_b))
+}
+
+fn split_epoch(version: &str) -> (u64, &str) {
+    match version.split_once(':') {
+        Some((epoch, rest)) if !epoch.is_empty() && epoch.bytes().all(|b| b.is_ascii_digit()) => {
+            (epoch.parse().unwrap_or(0), rest)
+        }
+        _ => (0, version),
+    }
+}
+
+fn compare_segments(a: &str, b: &str) -> Ordering {
+    let mut a = a.as_bytes();
+    let mut b = b.as_bytes();
+
+    loop {
+        // Separators only split segments, they never compare against each other
+        a = trim_separators(a);
+        b = trim_separators(b);
+
+        match (a.first(), b.first()) {
+            (Some(b'~'), Some(b'~')) => {
+                a = &a[1..];
+                b = &b[1..];
+                continue;
+            }
+            (Some(b'~'), _) => return Ordering::Less,
+            (_, Some(b'~')) => return Ordering::Greater,
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            _ => {}
+        }
+
+        let numeric = a[0].is_ascii_digit();
+        let (segment_a, rest_a) = take_segment(a, numeric);
+        let (segment_b, rest_b) = take_segment(b, numeric);
+
+        // The other side has a letter run where this one has digits
+        if segment_b.is_empty() {
+            return if numeric { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let order = if numeric {
+            let segment_a = trim_leading_zeros(segment_a);
+            let segment_b = trim_leading_zeros(segment_b);
+            segment_a.len().cmp(&segment_b.len()).then_with(|| segment_a.cmp(segment_b))
+        } else {
+            segment_a.cmp(segment_b)
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+
+        a = rest_a;
+        b = rest_b;
+    }
+}
+
+fn trim_separators(s: &[u8]) -> &[u8] {
+    let start = s.iter().position(|&c| c.is_ascii_alphanumeric() || c == b'~').unwrap_or(s.len());
+    &s[start..]
+}
+
+fn take_segment(s: &[u8], numeric: bool) -> (&[u8], &[u8]) {
+    let end = s
+        .iter()
+        .position(|c| if numeric { !c.is_ascii_digit() } else { !c.is_ascii_alphabetic() })
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn trim_leading_zeros(s: &[u8]) -> &[u8] {
+    let start = s.iter().position(|&c| c != b'0').unwrap_or(s.len());
+    &s[start..]
+}
+
+// Keep one entry per (source, name): the one with the highest version. Different sources
+// are left alone, and the kept entry counts as installed if any of its versions was.
+// Order follows each name's first appearance
+pub fn latest_versions_only(packages: Vec<Package>) -> Vec<Package> {
+    let mut positions: HashMap<(String, String), usize> = HashMap::new();
+    let mut latest: Vec<Package> = Vec::with_capacity(packages.len());
+
+    for package in packages {
+        let key = (package.source.clone(), package.name.clone());
+        let Some(&position) = positions.get(&key) else {
+            positions.insert(key, latest.len());
+            latest.push(package);
+            continue;
+        };
+
+        let kept = &mut latest[position];
+        let installed = kept.installed || package.installed;
+        let newer = match (&package.version, &kept.version) {
+            (Some(new), Some(old)) => compare_versions(new, old) == Ordering::Greater,
+            (Some(_), None) => true,
+            _ => false,
+        };
+        if newer {
+            *kept = package;
+        }
+        kept.installed = installed;
+    }
+
+    latest
+}
+
+// Installed packages that the same source has a newer version of, for the "N updates
+// available" summary. Expects the installed copies and the available ones side by side,
+// as the package list holds them before latest_versions_only collapses them
+pub fn compute_upgradable(packages: &[Package]) -> Vec<&Package> {
+    let mut newest: HashMap<(&str, &str), &str> = HashMap::new();
+    for package in packages.iter().filter(|p| !p.installed) {
+        let Some(version) = package.version.as_deref() else {
+            continue;
+        };
+        newest
+            .entry((package.source.as_str(), package.name.as_str()))
+            .and_modify(|current| {
+                if compare_versions(version, current) == Ordering::Greater {
+                    *current = version;
+                }
+            })
+            .or_insert(version);
+    }
+
+    packages
+        .iter()
+        .filter(|p| p.installed)
+        .filter(|p| {
+            let Some(installed) = p.version.as_deref() else {
+                return false;
+            };
+            newest
+                .get(&(p.source.as_str(), p.name.as_str()))
+                .is_some_and(|available| compare_versions(available, installed) == Ordering::Greater)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, source: &str, version: &str, installed: bool) -> Package {
+        Package {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            installed,
+            source: source.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn compare_versions_orders_like_pacman() {
+        let cases = [
+            ("1:1.0", "2.0", Ordering::Greater),
+            ("1:1.0", "1:1.0", Ordering::Equal),
+            ("0:2.0", "2.0", Ordering::Equal),
+            ("1.10", "1.9", Ordering::Greater),
+            ("1.0.1", "1.0", Ordering::Greater),
+            ("1.0.1", "1.0a", Ordering::Greater),
+            ("1.0a", "1.0b", Ordering::Less),
+            ("1.0-1", "1.0-2", Ordering::Less),
+            ("1.0-r1", "1.0", Ordering::Greater),
+            ("1.0-r2", "1.0-r10", Ordering::Less),
+            ("1.0~rc1", "1.0", Ordering::Less),
+            ("1.0~rc1", "1.0~rc2", Ordering::Less),
+            ("1.01", "1.1", Ordering::Equal),
+            ("1_0", "1.0", Ordering::Equal),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(compare_versions(a, b), expected, "{} vs {}", a, b);
+            assert_eq!(compare_versions(b, a), expected.reverse(), "{} vs {}", b, a);
+        }
+    }
+
+    #[test]
+    fn compute_upgradable_finds_newer_versions_from_the_same_source() {
+        let packages = vec![
+            package("vim", "pacman", "9.0-1", true),
+            package("vim", "pacman", "9.1-1", false),
+            package("vim", "pacman", "8.2-1", false),
+            package("nano", "pacman", "7.2-1", true),
+            package("nano", "pacman", "7.2-1", false),
+            package("curl", "pacman", "8.5-1", true),
+            package("curl", "apt", "9.0", false),
+            package("git", "pacman", "1:2.0-1", true),
+            package("git", "pacman", "2.44-1", false),
+        ];
+        let upgradable: Vec<&str> = compute_upgradable(&packages).iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(upgradable, vec!["vim"]);
+    }
+
+    #[test]
+    fn latest_versions_only_keeps_the_newest_per_source() {
+        let latest = latest_versions_only(vec![
+            package("vim", "pacman", "9.0-1", true),
+            package("nano", "pacman", "7.2-1", false),
+            package("vim", "pacman", "9.1-1", false),
+            package("vim", "apt", "8.2", false),
+            package("vim", "pacman", "1:8.0-1", false),
+        ]);
+        let kept: Vec<(&str, &str, Option<&str>, bool)> = latest
+            .iter()
+            .map(|p| (p.name.as_str(), p.source.as_str(), p.version.as_deref(), p.installed))
+            .collect();
+        assert_eq!(kept, vec![
+            ("vim", "pacman", Some("1:8.0-1"), true),
+            ("nano", "pacman", Some("7.2-1"), false),
+            ("vim", "apt", Some("8.2"), false),
+        ]);
+    }
+}
@@ -0,0 +1,3 @@
+// The binary lives in main.rs; the modules are exposed here so benches can use them
+pub mod core;
+pub mod ui;
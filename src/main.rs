@@ -1,27 +1,127 @@
-mod ui;
-mod core;
-
 use std::env;
+use std::path::PathBuf;
+
+use pmux::core::{command_log, config::Config, logging};
+use pmux::ui::{self, ActivePane};
+
+const USAGE: &str = "Usage: pmux [--config <dir>] [--log-file <path>] [--pane <results|search|installed|terminal>] [--read-only] [--verbose] [-S] [query]";
+
+struct Args {
+    query: Option<String>,
+    pane: Option<ActivePane>,
+    config_dir: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+    read_only: bool,
+    verbose: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        query: None,
+        pane: None,
+        config_dir: None,
+        log_file: None,
+        read_only: false,
+        verbose: false,
+    };
+    let mut iter = env::args().skip(1);
+    
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--config" => {
+                let dir = iter.next().ok_or("--config requires a directory")?;
+                args.config_dir = Some(PathBuf::from(dir));
+            }
+            // Where executed commands are recorded (defaults to the cache dir)
+            "--log-file" => {
+                let path = iter.next().ok_or("--log-file requires a path")?;
+                args.log_file = Some(PathBuf::from(path));
+            }
+            // Pane focused at startup
+            "--pane" => {
+                let name = iter.next().ok_or("--pane requires a pane name")?;
+                args.pane = Some(parse_pane(&name)?);
+            }
+            // Browse only: installing and undoing are disabled
+            "--read-only" => {
+                args.read_only = true;
+            }
+            // Debug logging to the cache dir (same as RUST_LOG=debug)
+            "-v" | "--verbose" => {
+                args.verbose = true;
+            }
+            // Single-shot mode with query
+            "-S" => {
+                args.query = iter.next();
+            }
+            _ if arg.starts_with("--config=") => {
+                args.config_dir = Some(PathBuf::from(&arg["--config=".len()..]));
+            }
+            _ if arg.starts_with("--pane=") => {
+                args.pane = Some(parse_pane(&arg["--pane=".len()..])?);
+            }
+            _ if arg.starts_with("--log-file=") => {
+                args.log_file = Some(PathBuf::from(&arg["--log-file=".len()..]));
+            }
+            _ if arg.starts_with("--") => {
+                return Err(format!("Unknown option: {}", arg));
+            }
+            // Normal mode with optional initial query
+            _ => {
+                if args.query.is_none() {
+                    args.query = Some(arg);
+                }
+            }
+        }
+    }
+    
+    Ok(args)
+}
+
+fn parse_pane(name: &str) -> Result<ActivePane, String> {
+    ActivePane::from_name(name).ok_or_else(|| format!("Unknown pane: {}", name))
+}
 
-#[tokio::main]
-async fn main() {
+fn main() {
     // Set up panic handler to restore terminal
     std::panic::set_hook(Box::new(|_| {
         use crossterm::{execute, terminal};
         let _ = terminal::disable_raw_mode();
-        let _ = execute!(std::io::stdout(), terminal::LeaveAlternateScreen);
+        let _ = execute!(std::io::stdout(), terminal::LeaveAlternateScreen, crossterm::event::DisableBracketedPaste);
     }));
-    let args: Vec<String> = env::args().skip(1).collect();
     
-    let initial_query = if args.get(0) == Some(&"-S".to_string()) {
-        // Single-shot mode with query
-        args.get(1).cloned()
-    } else {
-        // Normal mode with optional initial query
-        args.get(0).cloned()
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
     };
+    
+    logging::init(args.verbose);
+    
+    if let Some(dir) = args.config_dir {
+        Config::set_config_dir(dir);
+    }
+    if let Some(path) = args.log_file {
+        command_log::set_log_file(path);
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error: failed to start async runtime: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let result = runtime.block_on(ui::run_tui(args.query, args.pane, args.read_only));
+    
+    // Package listing can still be running external commands on the blocking pool;
+    // don't make the user wait for it after quitting
+    runtime.shutdown_background();
 
-    match ui::run_tui(initial_query).await {
+    match result {
         Ok(()) => {},
         Err(e) => {
             eprintln!("Error: {}", e);
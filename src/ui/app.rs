@@ -1,8 +1,106 @@
-use crate::core::package_managers::Package;
+use crate::core::package_managers::{binary_in_path, Package, PackageManagerConfig, PackageManagerRegistry};
 use crate::core::local::LocalPackageManager;
-use crate::core::aur::AurClient;
-use std::collections::{HashMap, HashSet};
-use std::time::{Duration, Instant};
+use crate::core::process;
+use crate::core::pacman_db::{cached_versions, strip_version_constraint, PACKAGE_CACHE_DIR};
+use crate::core::aur::{self, AurClient};
+use crate::core::cache::{Cache, LastInstall};
+use crate::core::config::{ignore_pattern_regex, Config, EmptyQueryShows, InstallBatching, InstalledSort};
+use crate::core::version::{compare_versions, compute_upgradable, latest_versions_only};
+use crate::ui::layout::{pane_areas, PaneAreas};
+use crate::ui::result_format::ResultFormat;
+use ratatui::layout::Rect;
+use ratatui::widgets::ListState;
+use regex::Regex;
+use std::cell::{Ref, RefCell};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::process::ExitStatus;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const MAX_SEARCH_HISTORY: usize = 100;
+// Letters typed closer together than this extend the same jump-to-name
+const TYPEAHEAD_WINDOW: Duration = Duration::from_millis(1000);
+// Neighbouring details are only prefetched while fewer fetches than this are running
+const MAX_DETAILS_IN_FLIGHT: usize = 3;
+
+// How one command of an install batch went; None if it couldn't be started
+pub struct CommandResult {
+    pub command: String,
+    pub status: Option<ExitStatus>,
+}
+
+impl CommandResult {
+    pub fn succeeded(&self) -> bool {
+        self.status.is_some_and(|status| status.success())
+    }
+    
+    // Stopped with Ctrl+C, either killed by the SIGINT itself or exiting with the
+    // conventional 128 + SIGINT a shell reports for it
+    pub fn interrupted(&self) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if self.status.is_some_and(|status| status.signal() == Some(2)) {
+                return true;
+            }
+        }
+        self.status.is_some_and(|status| status.code() == Some(130))
+    }
+}
+
+// The search box split into filters and the free text to match. A leading manager
+// name followed by a space or `*` (`apt vim`, `nix*`) limits results to that source,
+// and `repo:<name>` to one repository
+pub struct SearchQuery {
+    pub source: Option<String>,
+    pub repo: Option<String>,
+    pub text: String,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str, sources: &[String]) -> Self {
+        let mut source = None;
+        let mut repo = None;
+        let mut words: Vec<&str> = input.split_whitespace().collect();
+        
+        // A bare manager name is still a plain search until something follows it
+        if let Some(&first) = words.first() {
+            let name = first.strip_suffix('*').unwrap_or(first);
+            let terminated = name.len() < first.len() || input.trim_start().len() > first.len();
+            if terminated && sources.iter().any(|source| source.eq_ignore_ascii_case(name)) {
+                source = Some(name.to_lowercase());
+                words.remove(0);
+            }
+        }
+        
+        words.retain(|word| match word.strip_prefix("repo:") {
+            Some(name) if !name.is_empty() => {
+                repo = Some(name.to_lowercase());
+                false
+            }
+            _ => true,
+        });
+        
+        SearchQuery {
+            source,
+            repo,
+            text: words.join(" ").to_lowercase(),
+        }
+    }
+    
+    fn matches_filters(&self, package: &Package) -> bool {
+        let source_matches = match &self.source {
+            Some(source) => package.source.eq_ignore_ascii_case(source),
+            None => true,
+        };
+        let repo_matches = match &self.repo {
+            Some(repo) => package.repo.as_ref().is_some_and(|r| r.eq_ignore_ascii_case(repo)),
+            None => true,
+        };
+        source_matches && repo_matches
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InputMode {
@@ -19,6 +117,67 @@ pub enum ActivePane {
     Terminal,
 }
 
+impl ActivePane {
+    // Names accepted by `--pane`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "results" => Some(ActivePane::Results),
+            "search" => Some(ActivePane::Search),
+            "installed" => Some(ActivePane::Installed),
+            "terminal" => Some(ActivePane::Terminal),
+            _ => None,
+        }
+    }
+}
+
+// Text drawn every frame but only formatted again when what it shows changes, like the
+// counts in the pane titles while nothing is scrolled or filtered
+pub struct CachedText<K> {
+    cache: RefCell<Option<(K, String)>>,
+}
+
+impl<K> Default for CachedText<K> {
+    fn default() -> Self {
+        CachedText { cache: RefCell::new(None) }
+    }
+}
+
+impl<K: PartialEq> CachedText<K> {
+    pub fn get(&self, key: K, build: impl FnOnce() -> String) -> Ref<'_, str> {
+        let stale = self.cache.borrow().as_ref().is_none_or(|(cached, _)| *cached != key);
+        if stale {
+            let text = build();
+            *self.cache.borrow_mut() = Some((key, text));
+        }
+        Ref::map(self.cache.borrow(), |cache| cache.as_ref().map_or("", |(_, text)| text.as_str()))
+    }
+}
+
+// selection_conflicts() with the package lists generation and selection it was worked out from
+type CachedConflicts = (u64, HashSet<String>, BTreeMap<String, Vec<String>>);
+
+// One row of the managers overview
+pub struct ManagerStatus {
+    pub name: String,
+    pub stratum: Option<String>,
+    pub detected: bool, // False for enabled_pm entries that weren't found on this system
+    pub enabled: bool, // Listed in [pm] enabled_pm
+    pub executable: Option<(String, bool)>, // From its TOML definition, and whether it's on PATH
+}
+
+// Older versions of an installed package offered by `D` in the Installed pane
+pub struct DowngradePicker {
+    pub package: String,
+    pub installed_version: Option<String>,
+    pub options: Vec<DowngradeOption>,
+    pub cursor: usize,
+}
+
+pub struct DowngradeOption {
+    pub label: String, // The version, or what a downgrade helper does instead
+    pub command: String,
+}
+
 pub struct App {
     // Core state
     pub should_quit: bool,
@@ -28,22 +187,49 @@ pub struct App {
     // Search state
     pub search_input: String,
     pub cursor_position: usize,
+    pub search_history: Vec<String>, // Submitted queries, oldest first
+    pub history_index: Option<usize>, // Position while recalling with Up/Down
     
     // Results state
     pub packages: Vec<Package>,
-    pub filtered_packages: Vec<Package>,
+    pub filtered_indices: Vec<usize>, // Positions in `packages` matching the current search
+    search_keys: Vec<String>, // Lowercased searchable text of each package, parallel to `packages`
+    ignore_patterns: Vec<Regex>, // Compiled [search] ignore_patterns
+    ignored: Vec<bool>, // Whether each package matches ignore_patterns, parallel to `packages`
+    pub hide_noise: bool, // Hide debug, documentation and lib32 variants (see is_noise_package)
+    search_text: String, // Lowercased free text the current results were filtered with
+    filtered_input: String, // search_input as of the last filter, so an unchanged query isn't refiltered
+    pub name_only_search: bool, // Match the query against names only, not descriptions and provides
+    pub empty_query_shows: EmptyQueryShows, // Results listed while the search field is empty
+    pub last_filter_time: Option<Duration>, // How long the latest filter_packages() took
+    pub updates_available: Option<usize>, // With [ui] check_updates_on_start, counted each time packages load
+    pub category_tree: bool, // Group emerge results under collapsible category headers
+    pub expanded_categories: HashSet<String>, // Categories whose packages the tree shows
+    pub category_headers: HashMap<usize, (String, usize)>, // Results row -> category and package count
+    pub group_by_source: bool, // Section results by source under header rows
+    pub source_headers: HashMap<usize, (String, usize)>, // Results row -> source and result count; never highlighted
     pub selected_index: usize,
     pub scroll_offset: usize,
     
     // Installed packages
     pub installed_packages: Vec<Package>,
+    pub installed_view: Vec<usize>, // Installed pane rows as indices into installed_packages
+    pub orphans: Option<HashSet<String>>, // While filtering to orphans: the "source:name" keys found so far
+    pub pending_orphan_queries: Vec<(String, String)>, // (source, orphans command) for the main loop to run
+    pub pending_group_expansions: Vec<String>, // pacman groups for the main loop to list with `pacman -Sg`
     pub installed_selected: usize,
     pub installed_scroll: usize,
+    pub installed_size_total: Option<u64>, // Summed over installed packages whose size is known
+    pub installed_sort: InstalledSort,
     
     // Package details
     pub package_details: HashMap<String, String>,
+    pub details_requested: HashSet<String>, // Keys with a fetch in flight or done
+    details_in_flight: usize, // Requested details that haven't arrived yet
     pub details_loading: bool,
     pub last_selection_time: Instant,
+    pub compare_packages: Option<(Package, Package)>, // Pinned side by side in the details pane
+    related_cursor: Option<(String, usize)>, // Highlighted package's key and the optional dependency picked with `r`
     
     // Search debouncing
     pub last_search_time: Instant,
@@ -51,16 +237,70 @@ pub struct App {
     
     // Package managers
     pub package_managers: Vec<LocalPackageManager>,
+    pub pm_registry: PackageManagerRegistry, // TOML-defined managers, for their install commands
     pub loading_complete: bool,
+    pub managers_overview: Option<Vec<ManagerStatus>>, // Shown as a popup while Some
+    pub managers_cursor: usize, // Highlighted row in the managers overview
+    pub configs_overview: bool, // Manager definitions popup, shown while true
+    pub configs_cursor: usize, // Highlighted definition, the ones that failed to load after the loaded ones
+    pub downgrade_picker: Option<DowngradePicker>, // Shown as a popup while Some
+    pub disabled_managers: HashSet<String>, // Managers whose packages are hidden this session
     
     // Multi-selection
     pub selected_packages: HashSet<String>, // Package names selected for installation
+    pub selection_cursor: usize, // Cursor within the selection review in the Terminal pane
+    pub pinned_versions: HashMap<String, String>, // Selected packages to install at a specific version
+    
+    // Command execution - key handlers queue commands, the main loop owns the terminal and runs them
+    pub pending_commands: Vec<String>,
+    pub confirm_commands: Vec<String>, // Privileged commands awaiting a yes/no from the user
+    pub confirming_selection: bool, // confirm_commands install the selection, rather than undo or re-run
+    stashed_selection: Option<(HashSet<String>, HashMap<String, String>)>, // Put back once a one-package install is confirmed or cancelled
+    pub pending_previews: Vec<String>, // Dry-run commands for the main loop to run in the background
+    pub preview_output: Vec<String>, // What the latest dry run printed, shown over the selection
+    pub previews_running: usize, // Dry runs started and not reported back yet
+    pub showing_preview: bool, // The Terminal pane shows preview_output instead of the selection
+    pub install_summary: Vec<CommandResult>, // Results of the last batch, shown until dismissed
+    queued_installs: HashMap<String, Vec<String>>, // Packages each queued command installs, recorded for undo
+    queued_removals: HashMap<String, Vec<String>>, // Packages each queued undo command removes
+    last_batch: Vec<String>, // Commands of the last executed batch, for re-running with `.`
+    last_batch_installs: HashMap<String, Vec<String>>, // queued_installs/queued_removals of that batch
+    last_batch_removals: HashMap<String, Vec<String>>,
+    pub terminal_output: Vec<String>, // What was run and how it exited, shown in the Terminal pane
+    pub terminal_scroll: usize, // Lines scrolled back from the newest output
+    pub terminal_max_lines: usize,
+    pub view_command_log: bool,
+    pub edit_config: bool, // Open config.toml in $EDITOR and reload it, outside the TUI
+    pub reload_managers: bool, // Detect package managers and load their packages again
     
     // AUR client
     pub aur_client: AurClient,
+    pub aur_search_started: Option<Instant>, // Set while a live AUR search is in flight
     
     // UI state
+    pub config: Config, // Layout and colours the renderer draws with
     pub terminal_size: (u16, u16),
+    pub result_format: ResultFormat,
+    pub status_message: Option<(String, Instant)>,
+    pub read_only: bool, // --read-only: never run install or remove commands
+    pub wrap_navigation: bool, // Up on the first entry goes to the last and vice versa
+    pub scroll_margin: usize, // Rows kept visible above and below the cursor when scrolling
+    pub sudoers: String, // Privilege program that root commands are run through, e.g. sudo or doas
+    pub latest_version_only: bool, // Collapse versions of a package within a source to the newest
+    typeahead: Option<String>, // Letters typed so far for jump-to-name in the Results/Installed lists
+    typeahead_time: Instant,
+    clipboard: Option<arboard::Clipboard>,
+    
+    // Kept between frames so drawing an unchanged list doesn't rebuild them
+    pub results_title: CachedText<(usize, usize, usize, usize)>, // First and last shown row, rows, pane height
+    pub installed_title: CachedText<(usize, usize, usize, usize, Option<u64>, bool)>, // Same, plus total size and orphans
+    pub result_count_text: CachedText<(usize, u128)>, // Results and milliseconds the filter took
+    pub results_list_state: RefCell<ListState>,
+    pub installed_list_state: RefCell<ListState>,
+    packages_generation: u64, // Bumped whenever packages or installed_packages are replaced or added to
+    packages_by_name: HashMap<String, Vec<usize>>, // Package name -> indices into packages, in list order
+    packages_by_provides: HashMap<String, Vec<usize>>, // Virtual name -> indices of the packages providing it
+    conflicts: RefCell<Option<CachedConflicts>>,
 }
 
 impl Default for App {
@@ -72,37 +312,141 @@ impl Default for App {
             
             search_input: String::new(),
             cursor_position: 0,
+            search_history: Vec::new(),
+            history_index: None,
             
             packages: Vec::new(),
-            filtered_packages: Vec::new(),
+            filtered_indices: Vec::new(),
+            search_keys: Vec::new(),
+            ignore_patterns: Vec::new(),
+            ignored: Vec::new(),
+            hide_noise: true,
+            search_text: String::new(),
+            filtered_input: String::new(),
+            name_only_search: false,
+            empty_query_shows: EmptyQueryShows::All,
+            last_filter_time: None,
+            updates_available: None,
+            category_tree: false,
+            expanded_categories: HashSet::new(),
+            category_headers: HashMap::new(),
+            group_by_source: false,
+            source_headers: HashMap::new(),
             selected_index: 0,
             scroll_offset: 0,
             
             installed_packages: Vec::new(),
+            installed_view: Vec::new(),
+            orphans: None,
+            pending_orphan_queries: Vec::new(),
+            pending_group_expansions: Vec::new(),
             installed_selected: 0,
             installed_scroll: 0,
+            installed_size_total: None,
+            installed_sort: InstalledSort::Name,
             
             package_details: HashMap::new(),
+            details_requested: HashSet::new(),
+            details_in_flight: 0,
             details_loading: false,
             last_selection_time: Instant::now(),
+            compare_packages: None,
+            related_cursor: None,
             
             last_search_time: Instant::now(),
             search_debounce_ms: 150,
             
             package_managers: Vec::new(),
+            pm_registry: PackageManagerRegistry::new(),
             loading_complete: false,
+            managers_overview: None,
+            managers_cursor: 0,
+            configs_overview: false,
+            configs_cursor: 0,
+            downgrade_picker: None,
+            disabled_managers: HashSet::new(),
             
             selected_packages: HashSet::new(),
+            selection_cursor: 0,
+            pinned_versions: HashMap::new(),
+            
+            pending_commands: Vec::new(),
+            pending_previews: Vec::new(),
+            preview_output: Vec::new(),
+            previews_running: 0,
+            showing_preview: false,
+            confirm_commands: Vec::new(),
+            confirming_selection: false,
+            stashed_selection: None,
+            install_summary: Vec::new(),
+            queued_installs: HashMap::new(),
+            queued_removals: HashMap::new(),
+            last_batch: Vec::new(),
+            last_batch_installs: HashMap::new(),
+            last_batch_removals: HashMap::new(),
+            terminal_output: Vec::new(),
+            terminal_scroll: 0,
+            terminal_max_lines: 1000,
+            view_command_log: false,
+            edit_config: false,
+            reload_managers: false,
+            
             aur_client: AurClient::new(),
+            aur_search_started: None,
             
+            config: Config::default(),
             terminal_size: (80, 24),
+            result_format: ResultFormat::default(),
+            status_message: None,
+            read_only: false,
+            wrap_navigation: false,
+            scroll_margin: 0,
+            sudoers: "sudo".to_string(),
+            latest_version_only: false,
+            typeahead: None,
+            typeahead_time: Instant::now(),
+            clipboard: None,
+            results_title: CachedText::default(),
+            installed_title: CachedText::default(),
+            result_count_text: CachedText::default(),
+            results_list_state: RefCell::new(ListState::default()),
+            installed_list_state: RefCell::new(ListState::default()),
+            packages_generation: 0,
+            packages_by_name: HashMap::new(),
+            packages_by_provides: HashMap::new(),
+            conflicts: RefCell::new(None),
         }
     }
 }
 
 impl App {
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            config: Config::load().unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+    
+    // Copy the settings App keeps its own fields for out of the config, at startup and again
+    // whenever the config is edited. latest_version_only only takes effect on the next load
+    pub fn apply_config(&mut self, config: Config) {
+        self.result_format = ResultFormat::parse(&config.layout.result_format);
+        self.terminal_max_lines = config.main.terminal_max_lines.max(1);
+        self.wrap_navigation = config.ui.wrap_navigation;
+        self.scroll_margin = config.ui.scroll_margin;
+        self.latest_version_only = config.ui.latest_version_only;
+        self.name_only_search = config.ui.name_only_search;
+        self.empty_query_shows = config.ui.empty_query_shows;
+        self.installed_sort = config.ui.installed_sort;
+        self.disabled_managers = config.pm.disabled_pm.iter().cloned().collect();
+        self.set_ignore_patterns(&config.search.ignore_patterns);
+        self.sudoers = config.main.sudoers.clone();
+        process::set_load_timeout(Duration::from_secs(config.pm.load_timeout_secs));
+        aur::set_rpc_url(config.network.aur_rpc_url.clone());
+        self.config = config;
+        
+        self.sort_installed_packages();
+        self.filter_packages();
     }
     
     pub fn quit(&mut self) {
@@ -110,215 +454,1547 @@ impl App {
     }
     
     pub fn set_packages(&mut self, packages: Vec<Package>) {
-        self.packages = packages;
+        if self.config.ui.check_updates_on_start {
+            self.updates_available = Some(compute_upgradable(&packages).len());
+        }
+        self.packages = if self.latest_version_only {
+            latest_versions_only(packages)
+        } else {
+            packages
+        };
+        self.index_packages();
+        self.search_keys.clear();
+        self.ignored.clear();
+        self.filter_packages();
+    }
+    
+    // Name and provides lookups for related_package, rebuilt whenever packages changes
+    fn index_packages(&mut self) {
+        self.packages_generation += 1;
+        self.packages_by_name.clear();
+        self.packages_by_provides.clear();
+        for (index, package) in self.packages.iter().enumerate() {
+            self.packages_by_name.entry(package.name.clone()).or_default().push(index);
+            for provided in &package.provides {
+                self.packages_by_provides.entry(provided.clone()).or_default().push(index);
+            }
+        }
+    }
+    
+    // Invalid patterns are reported by Config::validate() before the TUI starts
+    pub fn set_ignore_patterns(&mut self, patterns: &[String]) {
+        self.ignore_patterns = patterns.iter().filter_map(|pattern| ignore_pattern_regex(pattern).ok()).collect();
+        self.ignored.clear();
+    }
+    
+    // Hide the highlighted result's package name for good by adding it to ignore_patterns
+    pub fn ignore_selected_package(&mut self) {
+        let Some(name) = self.get_selected_package().map(|package| package.name.clone()) else {
+            return;
+        };
+        
+        let mut patterns = self.config.search.ignore_patterns.clone();
+        if !patterns.contains(&name) {
+            patterns.push(name.clone());
+        }
+        match Config::save_ignore_patterns(&patterns) {
+            Ok(()) => self.set_status(format!("Hiding {} (added to ignore_patterns)", name)),
+            Err(e) => self.set_status(format!("Hiding {} for this session; failed to save the config: {}", name, e)),
+        }
+        self.set_ignore_patterns(&patterns);
+        self.config.search.ignore_patterns = patterns;
         self.filter_packages();
     }
     
     pub fn set_installed_packages(&mut self, packages: Vec<Package>) {
+        self.installed_size_total = sum_sizes(packages.iter().map(|p| p.installed_size));
         self.installed_packages = packages;
+        self.packages_generation += 1;
+        self.sort_installed_packages();
+    }
+    
+    // Managers list installed packages in whatever order they store them
+    fn sort_installed_packages(&mut self) {
+        match self.installed_sort {
+            InstalledSort::Name => self.installed_packages.sort_by(|a, b| {
+                a.name.cmp(&b.name).then_with(|| a.source.cmp(&b.source))
+            }),
+            InstalledSort::Source => self.installed_packages.sort_by(|a, b| {
+                a.source.cmp(&b.source).then_with(|| a.name.cmp(&b.name))
+            }),
+            // Largest first; packages without a known size go last
+            InstalledSort::Size => self.installed_packages.sort_by(|a, b| {
+                b.installed_size.cmp(&a.installed_size).then_with(|| a.name.cmp(&b.name))
+            }),
+        }
+        self.refresh_installed_view();
+    }
+    
+    // Which installed packages the pane lists: all of them, or only orphans while filtering
+    fn refresh_installed_view(&mut self) {
+        self.installed_view = (0..self.installed_packages.len())
+            .filter(|&index| {
+                let package = &self.installed_packages[index];
+                self.orphans.as_ref().is_none_or(|orphans| orphans.contains(&format!("{}:{}", package.source, package.name)))
+            })
+            .collect();
+        self.installed_selected = self.installed_selected.min(self.installed_view.len().saturating_sub(1));
+        self.scroll_installed_to_selection();
+    }
+    
+    pub fn highlighted_installed(&self) -> Option<&Package> {
+        self.installed_view.get(self.installed_selected).map(|&index| &self.installed_packages[index])
+    }
+    
+    // A manager's configured orphans_cmd, or the built-in one for managers pmux knows
+    fn orphans_command(&self, source: &str) -> Option<String> {
+        if let Some(command) = self.pm_registry.get_manager(source).and_then(|m| m.orphans_cmd.clone()) {
+            return Some(command);
+        }
+        match source {
+            "pacman" => Some("pacman -Qdtq".to_string()),
+            "apt" => Some("apt-get -s autoremove | sed -n 's/^Remv \\([^ ]*\\).*/\\1/p'".to_string()),
+            "dnf" => Some("dnf repoquery --unneeded --queryformat '%{name}\\n'".to_string()),
+            _ => None,
+        }
+    }
+    
+    // Show only the installed packages nothing needs any more (dependencies whose dependents
+    // are gone), asking each manager with an orphans command. Pressed again, show everything
+    pub fn toggle_orphans_filter(&mut self) {
+        if self.orphans.take().is_some() {
+            self.refresh_installed_view();
+            self.set_status("Showing all installed packages".to_string());
+            return;
+        }
+        
+        let sources: BTreeSet<&str> = self.installed_packages.iter().map(|p| p.source.as_str()).collect();
+        let queries: Vec<(String, String)> = sources
+            .into_iter()
+            .filter_map(|source| Some((source.to_string(), self.orphans_command(source)?)))
+            .collect();
+        if queries.is_empty() {
+            self.set_status("None of the installed packages' managers has an orphans_cmd".to_string());
+            return;
+        }
+        
+        self.orphans = Some(HashSet::new());
+        self.installed_selected = 0;
+        self.refresh_installed_view();
+        self.set_status("Looking for orphaned packages…".to_string());
+        self.pending_orphan_queries.extend(queries);
+    }
+    
+    // One manager's orphans command finished: the names it printed, one per line
+    pub fn add_orphans(&mut self, source: &str, output: Result<String, String>) {
+        let Some(orphans) = &mut self.orphans else {
+            return;
+        };
+        match output {
+            Ok(text) => {
+                for name in text.lines().filter_map(|line| line.split_whitespace().next()) {
+                    orphans.insert(format!("{}:{}", source, name));
+                }
+                self.refresh_installed_view();
+                self.set_status(match self.installed_view.len() {
+                    1 => "1 orphaned package".to_string(),
+                    count => format!("{} orphaned packages", count),
+                });
+            }
+            Err(e) => self.push_terminal_output(format!("Finding orphaned {} packages failed: {}", source, e)),
+        }
+    }
+    
+    // Re-sorts in place, keeping the highlighted package highlighted
+    pub fn cycle_installed_sort(&mut self) {
+        let highlighted = self.highlighted_installed().map(|p| (p.source.clone(), p.name.clone()));
+        
+        self.installed_sort = self.installed_sort.next();
+        self.sort_installed_packages();
+        
+        if let Some((source, name)) = highlighted {
+            let installed = &self.installed_packages;
+            if let Some(row) = self.installed_view.iter().position(|&i| installed[i].source == source && installed[i].name == name) {
+                self.installed_selected = row;
+                self.scroll_installed_to_selection();
+            }
+        }
+        self.set_status(format!("Installed packages sorted by {}", self.installed_sort.describe()));
     }
     
     pub fn filter_packages(&mut self) {
-        if self.search_input.is_empty() {
-            self.filtered_packages = self.packages.clone();
-        } else {
-            let query = self.search_input.to_lowercase();
-            self.filtered_packages = self.packages
+        // Runs on every keystroke against tens of thousands of packages, so match by index
+        // against text lowercased once up front rather than cloning and lowercasing each
+        // package every time (see benches/filter_packages.rs)
+        let started = Instant::now();
+        if self.search_keys.len() > self.packages.len() {
+            self.search_keys.clear();
+        }
+        let indexed = self.search_keys.len();
+        self.search_keys.extend(self.packages[indexed..].iter().map(search_key));
+        if self.ignored.len() > self.packages.len() {
+            self.ignored.clear();
+        }
+        let checked = self.ignored.len();
+        let patterns = &self.ignore_patterns;
+        self.ignored.extend(self.packages[checked..].iter().map(|pkg| patterns.iter().any(|regex| regex.is_match(&pkg.name))));
+        
+        let search = self.parse_search(&self.search_input);
+        let query = search.text.as_str();
+        let name_only = self.name_only_search;
+        let empty_query = self.search_input.trim().is_empty();
+        let disabled = &self.disabled_managers;
+        let ignored = &self.ignored;
+        let hide_noise = self.hide_noise;
+        // AUR results belong to paru
+        let shown = |index: usize, pkg: &Package| {
+            let hidden = ignored[index]
+                || (hide_noise && is_noise_package(&pkg.name))
+                || disabled.contains(if pkg.source == "aur" { "paru" } else { pkg.source.as_str() });
+            !hidden
+        };
+        self.filtered_indices = match self.empty_query_shows {
+            EmptyQueryShows::Nothing if empty_query => Vec::new(),
+            EmptyQueryShows::Installed if empty_query => self.packages
                 .iter()
-                .filter(|pkg| {
-                    pkg.name.to_lowercase().contains(&query) ||
-                    pkg.description.as_ref().map_or(false, |desc| desc.to_lowercase().contains(&query))
-                })
-                .cloned()
-                .collect();
+                .enumerate()
+                .filter(|&(index, pkg)| pkg.installed && shown(index, pkg))
+                .map(|(index, _)| index)
+                .collect(),
+            _ => {
+                // Several words match in any order; a single word is a plain substring match
+                let terms: Vec<&str> = query.split(' ').filter(|term| !term.is_empty()).collect();
+                let matching = self.packages
+                    .iter()
+                    .zip(&self.search_keys)
+                    .enumerate()
+                    .filter(|&(index, (pkg, key))| {
+                        // The name is the key's first line
+                        let text = if name_only { key.split('\n').next().unwrap_or_default() } else { key.as_str() };
+                        shown(index, pkg) && search.matches_filters(pkg) && terms.iter().all(|term| text.contains(term))
+                    });
+                
+                if terms.len() > 1 {
+                    let mut ranked: Vec<((Reverse<usize>, usize), usize)> = matching
+                        .map(|(index, (_, key))| (term_rank(key, &terms, name_only), index))
+                        .collect();
+                    ranked.sort_by_key(|&(rank, _)| rank);
+                    ranked.into_iter().map(|(_, index)| index).collect()
+                } else {
+                    matching.map(|(index, _)| index).collect()
+                }
+            }
+        };
+        self.search_text = search.text;
+        self.filtered_input = self.search_input.clone();
+        self.category_headers.clear();
+        if self.category_tree {
+            self.group_by_category();
         }
+        self.source_headers.clear();
+        if self.group_by_source {
+            self.group_by_source();
+        }
+        self.last_filter_time = Some(started.elapsed());
         
         // Reset selection
-        self.selected_index = 0;
+        self.selected_index = self.selectable_row(0, true).unwrap_or(0);
         self.scroll_offset = 0;
         self.last_selection_time = Instant::now();
     }
     
-    pub fn move_up(&mut self) {
-        match self.active_pane {
-            ActivePane::Results => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
-                    self.last_selection_time = Instant::now();
-                    
-                    // Adjust scroll
-                    if self.selected_index < self.scroll_offset {
-                        self.scroll_offset = self.selected_index;
+    // Portage names are "category/name". Move each emerge result up to where its category
+    // first appears, behind a header row, and leave the members of collapsed categories out.
+    // A header row points at its category's first package so filtered_indices stays a list
+    // of package indices; category_headers marks which rows are headers
+    fn group_by_category(&mut self) {
+        // Each entry is a category or a package from another source, in result order
+        let mut members: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut order: Vec<Result<&str, usize>> = Vec::new();
+        for &index in &self.filtered_indices {
+            let package = &self.packages[index];
+            match package.name.split_once('/').filter(|_| package.source == "emerge") {
+                Some((category, _)) => {
+                    let entry = members.entry(category).or_default();
+                    if entry.is_empty() {
+                        order.push(Ok(category));
                     }
+                    entry.push(index);
                 }
+                None => order.push(Err(index)),
             }
-            ActivePane::Installed => {
-                if self.installed_selected > 0 {
-                    self.installed_selected -= 1;
-                    
-                    if self.installed_selected < self.installed_scroll {
-                        self.installed_scroll = self.installed_selected;
-                    }
+        }
+        
+        let mut rows = Vec::with_capacity(self.filtered_indices.len());
+        let mut headers = HashMap::new();
+        for entry in order {
+            let category = match entry {
+                Ok(category) => category,
+                Err(index) => {
+                    rows.push(index);
+                    continue;
                 }
+            };
+            let packages = &members[category];
+            headers.insert(rows.len(), (category.to_string(), packages.len()));
+            rows.push(packages[0]);
+            if self.expanded_categories.contains(category) {
+                rows.extend(packages);
             }
-            _ => {}
         }
+        
+        self.filtered_indices = rows;
+        self.category_headers = headers;
     }
     
-    pub fn move_down(&mut self) {
-        match self.active_pane {
-            ActivePane::Results => {
-                if self.selected_index < self.filtered_packages.len().saturating_sub(1) {
-                    self.selected_index += 1;
-                    self.last_selection_time = Instant::now();
-                    
-                    // Adjust scroll based on terminal size
-                    let visible_items = self.get_results_visible_items();
-                    if self.selected_index >= self.scroll_offset + visible_items {
-                        self.scroll_offset = self.selected_index.saturating_sub(visible_items - 1);
-                    }
-                }
+    // Gather the results of each source together, sources in the order their best result
+    // appears and each keeping its own order, with a header row in front. Like category
+    // headers, a source header points at its first package; unlike them it can't be highlighted
+    fn group_by_source(&mut self) {
+        // A category header counts the packages it hides while collapsed; open, they have rows of their own
+        let mut order: HashMap<&str, usize> = HashMap::new();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (row, &index) in self.filtered_indices.iter().enumerate() {
+            let source = self.packages[index].source.as_str();
+            let next = order.len();
+            order.entry(source).or_insert(next);
+            *counts.entry(source).or_default() += match self.category_headers.get(&row) {
+                Some((category, _)) if self.expanded_categories.contains(category) => 0,
+                Some(&(_, count)) => count,
+                None => 1,
+            };
+        }
+        
+        // Rows keep their old position so category headers can follow them
+        let mut sorted: Vec<(usize, usize)> = self.filtered_indices.iter().copied().enumerate().collect();
+        sorted.sort_by_key(|&(_, index)| order[self.packages[index].source.as_str()]);
+        
+        let mut rows = Vec::with_capacity(sorted.len() + order.len());
+        let mut source_headers = HashMap::new();
+        let mut category_headers = HashMap::new();
+        let mut current: Option<&str> = None;
+        for (old_row, index) in sorted {
+            let source = self.packages[index].source.as_str();
+            if current != Some(source) {
+                current = Some(source);
+                source_headers.insert(rows.len(), (source.to_string(), counts[source]));
+                rows.push(index);
             }
-            ActivePane::Installed => {
-                if self.installed_selected < self.installed_packages.len().saturating_sub(1) {
-                    self.installed_selected += 1;
-                    
-                    let visible_items = self.get_installed_visible_items();
-                    if self.installed_selected >= self.installed_scroll + visible_items {
-                        self.installed_scroll = self.installed_selected.saturating_sub(visible_items - 1);
-                    }
-                }
+            if let Some(header) = self.category_headers.remove(&old_row) {
+                category_headers.insert(rows.len(), header);
             }
-            _ => {}
+            rows.push(index);
         }
+        
+        self.filtered_indices = rows;
+        self.category_headers = category_headers;
+        self.source_headers = source_headers;
     }
     
-    pub fn switch_pane(&mut self) {
-        self.active_pane = match self.active_pane {
-            ActivePane::Results => ActivePane::Search,
-            ActivePane::Search => ActivePane::Details,
-            ActivePane::Details => ActivePane::Installed,
-            ActivePane::Installed => ActivePane::Terminal,
-            ActivePane::Terminal => ActivePane::Results,
-        };
-        
-        if self.active_pane == ActivePane::Search {
-            self.input_mode = InputMode::Editing;
+    pub fn toggle_group_by_source(&mut self) {
+        self.group_by_source = !self.group_by_source;
+        self.filter_packages();
+        self.set_status(if self.group_by_source {
+            "Grouping results by source".to_string()
         } else {
-            self.input_mode = InputMode::Normal;
+            "Listing results from all sources together".to_string()
+        });
+    }
+    
+    // `row`, or the row next to it in that direction when it's a source header.
+    // None past either end of the results
+    pub fn selectable_row(&self, row: usize, down: bool) -> Option<usize> {
+        if !self.source_headers.contains_key(&row) {
+            return Some(row);
+        }
+        if down {
+            Some(row + 1).filter(|&row| row < self.filtered_indices.len())
+        } else {
+            row.checked_sub(1)
         }
     }
     
-    pub fn enter_search_mode(&mut self) {
-        self.active_pane = ActivePane::Search;
-        self.input_mode = InputMode::Editing;
+    fn is_header_row(&self, row: usize) -> bool {
+        self.category_headers.contains_key(&row) || self.source_headers.contains_key(&row)
     }
     
-    pub fn add_char(&mut self, c: char) {
-        if self.input_mode == InputMode::Editing {
-            self.search_input.insert(self.cursor_position, c);
-            self.cursor_position += 1;
-            self.last_search_time = Instant::now();
-        }
+    // Packages in the results list, leaving out the category and source header rows
+    pub fn result_count(&self) -> usize {
+        self.filtered_indices.len() - self.category_headers.len() - self.source_headers.len()
     }
     
-    pub fn delete_char(&mut self) {
-        if self.input_mode == InputMode::Editing && self.cursor_position > 0 {
-            self.search_input.remove(self.cursor_position - 1);
-            self.cursor_position -= 1;
-            self.last_search_time = Instant::now();
-        }
+    // Which of those packages is highlighted, counting from 1. On a category header it's
+    // the first package after it
+    pub fn selected_result_position(&self) -> usize {
+        let headers_before = self.category_headers
+            .keys()
+            .chain(self.source_headers.keys())
+            .filter(|&&row| row < self.selected_index)
+            .count();
+        (self.selected_index + 1 - headers_before).min(self.result_count())
     }
     
-    pub fn clear_search(&mut self) {
-        self.search_input.clear();
-        self.cursor_position = 0;
-        self.last_search_time = Instant::now();
-        self.filter_packages();
+    // The category whose header row is highlighted, if any
+    pub fn selected_category(&self) -> Option<&str> {
+        self.category_headers.get(&self.selected_index).map(|(category, _)| category.as_str())
     }
     
-    pub fn should_update_search(&self) -> bool {
-        self.last_search_time.elapsed() > Duration::from_millis(self.search_debounce_ms)
+    pub fn toggle_category_tree(&mut self) {
+        self.category_tree = !self.category_tree;
+        self.filter_packages();
+        self.set_status(if self.category_tree {
+            "Grouping Portage packages by category".to_string()
+        } else {
+            "Listing Portage packages flat".to_string()
+        });
     }
     
-    pub fn update_search_if_needed(&mut self) {
-        if self.should_update_search() {
-            self.filter_packages();
+    // Open or close the highlighted category, keeping its header highlighted
+    pub fn set_category_expanded(&mut self, expanded: bool) {
+        let Some(category) = self.selected_category().map(str::to_string) else {
+            return;
+        };
+        if expanded {
+            self.expanded_categories.insert(category.clone());
+        } else {
+            self.expanded_categories.remove(&category);
         }
+        
+        let (selected, scroll) = (self.selected_index, self.scroll_offset);
+        self.filter_packages();
+        self.selected_index = self.category_headers
+            .iter()
+            .find(|(_, (name, _))| *name == category)
+            .map_or(selected, |(&row, _)| row);
+        self.scroll_offset = scroll;
+        self.scroll_results_to_selection();
     }
     
-    pub fn get_selected_package(&self) -> Option<&Package> {
-        self.filtered_packages.get(self.selected_index)
+    // Detected managers and the enabled_pm entries, to explain why results may be missing
+    pub fn toggle_managers_overview(&mut self) {
+        if self.managers_overview.take().is_some() {
+            return;
+        }
+        
+        let enabled = &self.config.pm.enabled_pm;
+        let status = |name: &str, stratum: Option<String>, detected: bool| ManagerStatus {
+            name: name.to_string(),
+            stratum,
+            detected,
+            enabled: enabled.iter().any(|enabled_name| enabled_name == name),
+            executable: self.pm_registry
+                .get_manager(name)
+                .map(|config| (config.executable.clone(), self.pm_registry.is_available(config))),
+        };
+        
+        let mut rows: Vec<ManagerStatus> = self.package_managers
+            .iter()
+            .map(|pm| status(&pm.name, pm.stratum.clone(), true))
+            .collect();
+        for name in enabled {
+            if !self.package_managers.iter().any(|pm| &pm.name == name) {
+                rows.push(status(name, None, false));
+            }
+        }
+        self.managers_overview = Some(rows);
+        self.managers_cursor = 0;
     }
     
-    pub fn should_fetch_details(&self) -> bool {
-        self.last_selection_time.elapsed() > Duration::from_millis(300) &&
-        self.get_selected_package().is_some()
+    pub fn move_managers_cursor(&mut self, down: bool) {
+        let rows = self.managers_overview.as_ref().map_or(0, Vec::len);
+        self.managers_cursor = if down {
+            (self.managers_cursor + 1).min(rows.saturating_sub(1))
+        } else {
+            self.managers_cursor.saturating_sub(1)
+        };
     }
     
-    pub fn get_package_details(&self, package: &Package) -> Option<&String> {
-        let key = format!("{}:{}", package.source, package.name);
-        self.package_details.get(&key)
+    // Every definition loaded from pkgmanagers/, with its commands, and the files that failed to load
+    pub fn toggle_configs_overview(&mut self) {
+        self.configs_overview = !self.configs_overview;
+        self.configs_cursor = 0;
     }
     
-    pub fn set_package_details(&mut self, package: &Package, details: String) {
-        let key = format!("{}:{}", package.source, package.name);
-        self.package_details.insert(key, details);
+    // Loaded definitions by name, in the order the overview lists them
+    pub fn loaded_configs(&self) -> Vec<&PackageManagerConfig> {
+        let mut configs: Vec<&PackageManagerConfig> = self.pm_registry.managers.values().collect();
+        configs.sort_by(|a, b| a.name.cmp(&b.name));
+        configs
     }
     
-    pub fn get_results_visible_items(&self) -> usize {
-        // Calculate based on terminal height: total height - search (3) - details (8) - borders
-        let available_height = self.terminal_size.1.saturating_sub(13);
-        (available_height as usize).max(5) // Minimum 5 items visible
+    pub fn move_configs_cursor(&mut self, down: bool) {
+        let rows = self.pm_registry.managers.len() + self.pm_registry.load_errors.len();
+        self.configs_cursor = if down {
+            (self.configs_cursor + 1).min(rows.saturating_sub(1))
+        } else {
+            self.configs_cursor.saturating_sub(1).min(rows.saturating_sub(1))
+        };
     }
     
-    pub fn get_installed_visible_items(&self) -> usize {
-        // Right panel gets full height minus borders
-        let available_height = self.terminal_size.1.saturating_sub(2);
-        (available_height as usize).max(5) // Minimum 5 items visible
+    // Hide or show the highlighted manager's packages for the rest of the session
+    pub fn toggle_manager_at_cursor(&mut self) {
+        let Some(manager) = self.managers_overview.as_ref().and_then(|rows| rows.get(self.managers_cursor)) else {
+            return;
+        };
+        if !manager.detected {
+            return;
+        }
+        
+        let name = manager.name.clone();
+        if self.disabled_managers.remove(&name) {
+            self.set_status(format!("Showing {} packages", name));
+        } else {
+            self.set_status(format!("Hiding {} packages", name));
+            self.disabled_managers.insert(name);
+        }
+        self.filter_packages();
     }
     
-    // Multi-selection methods
-    pub fn toggle_package_selection(&mut self) {
-        if let Some(package) = self.get_selected_package() {
-            let package_key = format!("{}:{}", package.source, package.name);
-            if self.selected_packages.contains(&package_key) {
-                self.selected_packages.remove(&package_key);
-            } else {
-                self.selected_packages.insert(package_key);
-            }
+    // Remember the hidden managers in the config's [pm] disabled_pm
+    pub fn save_disabled_managers(&mut self) {
+        let mut disabled: Vec<String> = self.disabled_managers.iter().cloned().collect();
+        disabled.sort();
+        match Config::save_disabled_pm(&disabled) {
+            Ok(()) => self.set_status("Saved the hidden managers to the config".to_string()),
+            Err(e) => self.set_status(format!("Failed to save the config: {}", e)),
         }
+        self.config.pm.disabled_pm = disabled;
     }
     
-    pub fn is_package_selected(&self, package: &Package) -> bool {
-        let package_key = format!("{}:{}", package.source, package.name);
-        self.selected_packages.contains(&package_key)
+    pub fn toggle_noise_packages(&mut self) {
+        self.hide_noise = !self.hide_noise;
+        self.filter_packages();
+        self.set_status(if self.hide_noise {
+            "Hiding debug, doc and lib32 packages".to_string()
+        } else {
+            "Showing debug, doc and lib32 packages".to_string()
+        });
     }
     
-    pub fn clear_selection(&mut self) {
-        self.selected_packages.clear();
+    pub fn toggle_search_scope(&mut self) {
+        self.name_only_search = !self.name_only_search;
+        self.filter_packages();
+        self.set_status(format!("Searching {}", self.search_scope()));
     }
     
-    pub fn get_selected_count(&self) -> usize {
-        self.selected_packages.len()
+    pub fn cycle_empty_query_shows(&mut self) {
+        self.empty_query_shows = self.empty_query_shows.next();
+        if self.search_input.trim().is_empty() {
+            self.filter_packages();
+        }
+        self.set_status(format!("An empty search shows {}", self.empty_query_shows.describe()));
     }
     
-    pub fn get_selected_packages_list(&self) -> Vec<String> {
-        self.selected_packages.iter().cloned().collect()
+    pub fn search_scope(&self) -> &'static str {
+        if self.name_only_search { "names only" } else { "names and descriptions" }
     }
     
-    // Async search method for AUR integration
-    pub async fn search_aur_packages(&mut self, query: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Search AUR packages using the AUR client
-        let aur_results = self.aur_client.search(query).await?;
-        
-        // Add AUR results to the packages list
-        for aur_package in aur_results {
+    pub fn parse_search(&self, input: &str) -> SearchQuery {
+        SearchQuery::parse(input, &self.manager_names())
+    }
+    
+    // Names a search can be prefixed with, one per manager even across Bedrock strata
+    pub fn manager_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.package_managers.iter().map(|pm| pm.name.clone()).collect();
+        if names.iter().any(|name| name == "pacman" || name == "paru") {
+            names.push("aur".to_string());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+    
+    // Tab in the search field: complete a partly typed manager name at the start of the
+    // query, listing the candidates in the status bar when there's more than one.
+    // Returns false when there's nothing to complete
+    pub fn complete_manager_prefix(&mut self) -> bool {
+        let typed = self.search_input.trim_start().to_lowercase();
+        if typed.is_empty() || typed.contains(char::is_whitespace) || self.cursor_position != self.search_input.len() {
+            return false;
+        }
+        
+        let candidates: Vec<String> = self.manager_names()
+            .into_iter()
+            .filter(|name| name.starts_with(&typed))
+            .collect();
+        
+        let completion = match candidates.as_slice() {
+            [] => return false,
+            [only] => format!("{} ", only),
+            _ => {
+                self.set_status(format!("Managers: {}", candidates.join("  ")));
+                common_prefix(&candidates).to_string()
+            }
+        };
+        
+        if completion.len() > typed.len() {
+            self.search_input = completion;
+            self.cursor_position = self.search_input.len();
+            self.last_search_time = Instant::now();
+            self.history_index = None;
+        }
+        true
+    }
+    
+    // The virtual name a result was matched through, when its own name doesn't match the query
+    pub fn matched_provide<'a>(&self, package: &'a Package) -> Option<&'a str> {
+        // Rendered for every visible row, so reuse the query parsed when filtering
+        let query = self.search_text.as_str();
+        if query.is_empty() || package.provides.is_empty() || package.name.to_lowercase().contains(query) {
+            return None;
+        }
+        package.provides
+            .iter()
+            .find(|provide| provide.to_lowercase().contains(query))
+            .map(String::as_str)
+    }
+    
+    pub fn move_up(&mut self) {
+        match self.active_pane {
+            ActivePane::Results => {
+                let above = self.selected_index.checked_sub(1).and_then(|row| self.selectable_row(row, false));
+                if let Some(row) = above {
+                    self.selected_index = row;
+                    self.last_selection_time = Instant::now();
+                    self.scroll_results_to_selection();
+                } else if self.wrap_navigation && !self.filtered_indices.is_empty() {
+                    self.selected_index = self.filtered_indices.len() - 1;
+                    self.last_selection_time = Instant::now();
+                    self.scroll_results_to_selection();
+                }
+            }
+            ActivePane::Installed => {
+                if self.installed_selected > 0 {
+                    self.installed_selected -= 1;
+                    self.scroll_installed_to_selection();
+                } else if self.wrap_navigation && !self.installed_view.is_empty() {
+                    self.installed_selected = self.installed_view.len() - 1;
+                    self.scroll_installed_to_selection();
+                }
+            }
+            ActivePane::Terminal if self.selected_packages.is_empty() || self.showing_preview => {
+                // Stop once the oldest line reaches the top of the pane
+                let max_scroll = self.terminal_lines().len().saturating_sub(self.get_terminal_visible_lines());
+                self.terminal_scroll = (self.terminal_scroll + 1).min(max_scroll);
+            }
+            ActivePane::Terminal => {
+                self.selection_cursor = self.selection_cursor.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+    
+    pub fn move_down(&mut self) {
+        match self.active_pane {
+            ActivePane::Results => {
+                let below = Some(self.selected_index + 1)
+                    .filter(|&row| row < self.filtered_indices.len())
+                    .and_then(|row| self.selectable_row(row, true));
+                if let Some(row) = below {
+                    self.selected_index = row;
+                    self.last_selection_time = Instant::now();
+                    self.scroll_results_to_selection();
+                } else if self.wrap_navigation && !self.filtered_indices.is_empty() {
+                    self.selected_index = self.selectable_row(0, true).unwrap_or(0);
+                    self.scroll_offset = 0;
+                    self.last_selection_time = Instant::now();
+                }
+            }
+            ActivePane::Installed => {
+                if self.installed_selected < self.installed_view.len().saturating_sub(1) {
+                    self.installed_selected += 1;
+                    self.scroll_installed_to_selection();
+                } else if self.wrap_navigation && !self.installed_view.is_empty() {
+                    self.installed_selected = 0;
+                    self.installed_scroll = 0;
+                }
+            }
+            ActivePane::Terminal if self.selected_packages.is_empty() || self.showing_preview => {
+                self.terminal_scroll = self.terminal_scroll.saturating_sub(1);
+            }
+            ActivePane::Terminal => {
+                let last = self.selected_packages.len().saturating_sub(1);
+                self.selection_cursor = (self.selection_cursor + 1).min(last);
+            }
+            _ => {}
+        }
+    }
+    
+    pub fn typeahead_active(&self) -> bool {
+        self.typeahead.is_some() && self.typeahead_time.elapsed() < TYPEAHEAD_WINDOW
+    }
+    
+    // Begin an empty jump-to-name, so the next letters go to it even if they're bound
+    pub fn start_typeahead(&mut self) {
+        self.typeahead = Some(String::new());
+        self.typeahead_time = Instant::now();
+        self.set_status("Jump to: ".to_string());
+    }
+    
+    // Move the cursor to the first entry in the focused list whose name starts with
+    // the letters typed so far
+    pub fn jump_to_typed(&mut self, c: char) {
+        if !self.typeahead_active() {
+            self.typeahead = Some(String::new());
+        }
+        let typed = self.typeahead.get_or_insert_with(String::new);
+        typed.extend(c.to_lowercase());
+        let status = format!("Jump to: {}", typed);
+        self.typeahead_time = Instant::now();
+        self.set_status(status);
+        
+        let prefix = self.typeahead.as_deref().unwrap_or_default();
+        match self.active_pane {
+            ActivePane::Results => {
+                let found = self.filtered_indices
+                    .iter()
+                    .position(|&index| self.search_keys[index].starts_with(prefix));
+                if let Some(position) = found.and_then(|position| self.selectable_row(position, true)) {
+                    self.selected_index = position;
+                    self.last_selection_time = Instant::now();
+                    self.scroll_results_to_selection();
+                }
+            }
+            ActivePane::Installed => {
+                let installed = &self.installed_packages;
+                let found = self.installed_view
+                    .iter()
+                    .position(|&index| installed[index].name.to_lowercase().starts_with(prefix));
+                if let Some(position) = found {
+                    self.installed_selected = position;
+                    self.scroll_installed_to_selection();
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    pub fn switch_pane(&mut self) {
+        self.active_pane = match self.active_pane {
+            ActivePane::Results => ActivePane::Search,
+            ActivePane::Search => ActivePane::Details,
+            ActivePane::Details => ActivePane::Installed,
+            ActivePane::Installed => ActivePane::Terminal,
+            ActivePane::Terminal => ActivePane::Results,
+        };
+        
+        if self.active_pane == ActivePane::Search {
+            self.input_mode = InputMode::Editing;
+        } else {
+            self.input_mode = InputMode::Normal;
+        }
+    }
+    
+    pub fn enter_search_mode(&mut self) {
+        self.active_pane = ActivePane::Search;
+        self.input_mode = InputMode::Editing;
+    }
+    
+    pub fn add_char(&mut self, c: char) {
+        if self.input_mode == InputMode::Editing {
+            self.search_input.insert(self.cursor_position, c);
+            self.cursor_position += 1;
+            self.last_search_time = Instant::now();
+            self.history_index = None;
+        }
+    }
+    
+    pub fn delete_char(&mut self) {
+        if self.input_mode == InputMode::Editing && self.cursor_position > 0 {
+            self.search_input.remove(self.cursor_position - 1);
+            self.cursor_position -= 1;
+            self.last_search_time = Instant::now();
+            self.history_index = None;
+        }
+    }
+    
+    // Insert clipboard text at the cursor, flattening multi-line content
+    pub fn paste_from_clipboard(&mut self) {
+        if self.input_mode != InputMode::Editing {
+            return;
+        }
+
+        if let Ok(text) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            self.paste_text(&text);
+        }
+    }
+
+    // Insert pasted text at the cursor and filter once, rather than once per character.
+    // Newlines become spaces so a pasted list of names turns into one query
+    pub fn paste_text(&mut self, text: &str) {
+        if self.input_mode != InputMode::Editing {
+            return;
+        }
+
+        let text: String = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if text.is_empty() {
+            return;
+        }
+
+        self.search_input.insert_str(self.cursor_position, &text);
+        self.cursor_position += text.len();
+        self.last_search_time = Instant::now();
+        self.history_index = None;
+        self.filter_packages();
+    }
+
+    // Remember a submitted query, skipping repeats of the most recent one
+    pub fn push_search_history(&mut self) {
+        self.history_index = None;
+        let query = self.search_input.trim();
+        if query.is_empty() || self.search_history.last().map(String::as_str) == Some(query) {
+            return;
+        }
+        
+        self.search_history.push(query.to_string());
+        if self.search_history.len() > MAX_SEARCH_HISTORY {
+            self.search_history.remove(0);
+        }
+        if let Err(e) = Cache::save_search_history(&self.search_history) {
+            log::warn!("Failed to save search history: {}", e);
+        }
+    }
+    
+    // Step to an older query; returns false when there is nothing to recall
+    pub fn recall_older_search(&mut self) -> bool {
+        let index = match self.history_index {
+            Some(0) => return true,
+            Some(index) => index - 1,
+            None if self.search_history.is_empty() => return false,
+            None => self.search_history.len() - 1,
+        };
+        self.set_recalled_search(Some(index));
+        true
+    }
+    
+    // Step to a newer query, back to an empty field past the newest; false if not recalling
+    pub fn recall_newer_search(&mut self) -> bool {
+        match self.history_index {
+            Some(index) if index + 1 < self.search_history.len() => self.set_recalled_search(Some(index + 1)),
+            Some(_) => self.set_recalled_search(None),
+            None => return false,
+        }
+        true
+    }
+    
+    fn set_recalled_search(&mut self, index: Option<usize>) {
+        self.history_index = index;
+        self.search_input = index.map(|i| self.search_history[i].clone()).unwrap_or_default();
+        self.cursor_position = self.search_input.len();
+        self.last_search_time = Instant::now();
+        self.filter_packages();
+    }
+    
+    pub fn clear_search(&mut self) {
+        self.search_input.clear();
+        self.cursor_position = 0;
+        self.last_search_time = Instant::now();
+        self.filter_packages();
+    }
+    
+    pub fn should_update_search(&self) -> bool {
+        self.last_search_time.elapsed() > Duration::from_millis(self.search_debounce_ms)
+    }
+    
+    // Typing only updates search_input; filtering waits until typing pauses for
+    // search_debounce_ms, so long lists don't lag on every keystroke. Refiltering resets
+    // the cursor, so only do it once the query has actually changed
+    pub fn update_search_if_needed(&mut self) {
+        if self.should_update_search() && self.search_input != self.filtered_input {
+            self.filter_packages();
+        }
+    }
+    
+    // Leaving the search field before the debounce fires: filter now, so the cursor lands in
+    // up-to-date results instead of being reset when the filter catches up
+    pub fn apply_pending_search(&mut self) {
+        if self.search_input != self.filtered_input {
+            self.filter_packages();
+        }
+    }
+    
+    pub fn get_selected_package(&self) -> Option<&Package> {
+        if self.is_header_row(self.selected_index) {
+            return None;
+        }
+        self.filtered_indices.get(self.selected_index).map(|&index| &self.packages[index])
+    }
+
+    
+    // Search results come from the sync databases, so look up the installed
+    // copy of a package to learn why it is on the system
+    pub fn get_installed_copy<'a>(&'a self, package: &'a Package) -> Option<&'a Package> {
+        if package.install_reason.is_some() {
+            return Some(package);
+        }
+        self.installed_packages
+            .iter()
+            .find(|p| p.name == package.name && p.source == package.source)
+    }
+    
+    pub fn should_fetch_details(&self) -> bool {
+        self.last_selection_time.elapsed() > Duration::from_millis(300) &&
+        self.get_selected_package().is_some()
+    }
+    
+    pub fn get_package_details(&self, package: &Package) -> Option<&String> {
+        let key = format!("{}:{}", package.source, package.name);
+        self.package_details.get(&key)
+    }
+    
+    // Returns true the first time a package is asked for, so each fetch is only spawned once
+    pub fn mark_details_requested(&mut self, package: &Package) -> bool {
+        let key = format!("{}:{}", package.source, package.name);
+        let first = self.details_requested.insert(key);
+        if first {
+            self.details_in_flight += 1;
+        }
+        first
+    }
+    
+    // Neighbours of the highlighted result worth fetching ahead of time, the next one first,
+    // while few enough fetches are in flight
+    pub fn details_to_prefetch(&self) -> Vec<Package> {
+        let budget = MAX_DETAILS_IN_FLIGHT.saturating_sub(self.details_in_flight);
+        [self.selected_index.checked_add(1), self.selected_index.checked_sub(1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|position| self.filtered_indices.get(position))
+            .map(|&index| &self.packages[index])
+            .filter(|package| !self.details_requested.contains(&format!("{}:{}", package.source, package.name)))
+            .take(budget)
+            .cloned()
+            .collect()
+    }
+    
+    // Requested but not back yet
+    pub fn is_fetching_details(&self, package: &Package) -> bool {
+        let key = format!("{}:{}", package.source, package.name);
+        self.details_requested.contains(&key) && !self.package_details.contains_key(&key)
+    }
+    
+    pub fn set_package_details(&mut self, package: &Package, details: String) {
+        let key = format!("{}:{}", package.source, package.name);
+        if self.details_requested.contains(&key) && !self.package_details.contains_key(&key) {
+            self.details_in_flight = self.details_in_flight.saturating_sub(1);
+        }
+        self.package_details.insert(key, details);
+    }
+    
+    // Scroll just enough to show the highlighted entry with scroll_margin rows around it
+    pub fn scroll_results_to_selection(&mut self) {
+        let visible = self.get_results_visible_items();
+        let len = self.filtered_indices.len();
+        // Bring a group's header into view along with its first result
+        if let Some(header) = self.selected_index.checked_sub(1).filter(|row| self.source_headers.contains_key(row)) {
+            self.scroll_offset = scroll_to_show(header, self.scroll_offset, visible, 0, len);
+        }
+        self.scroll_offset = scroll_to_show(self.selected_index, self.scroll_offset, visible, self.scroll_margin, len);
+    }
+    
+    pub fn scroll_installed_to_selection(&mut self) {
+        let visible = self.get_installed_visible_items();
+        self.installed_scroll = scroll_to_show(self.installed_selected, self.installed_scroll, visible, self.scroll_margin, self.installed_view.len());
+    }
+    
+    // Rows inside a pane's borders, laid out the way render::draw lays it out
+    fn pane_rows(&self, pane: impl Fn(&PaneAreas) -> Rect) -> usize {
+        let (width, height) = self.terminal_size;
+        let areas = pane_areas(Rect::new(0, 0, width, height), &self.config.layout, self.details_height());
+        (pane(&areas).height.saturating_sub(2) as usize).max(1)
+    }
+    
+    pub fn get_results_visible_items(&self) -> usize {
+        self.pane_rows(|areas| areas.results)
+    }
+    
+    // The details pane grows to half the screen while comparing two packages
+    pub fn details_height(&self) -> u16 {
+        if self.compare_packages.is_some() {
+            (self.terminal_size.1 / 2).max(8)
+        } else {
+            8
+        }
+    }
+    
+    pub fn get_installed_visible_items(&self) -> usize {
+        self.pane_rows(|areas| areas.installed)
+    }
+    
+    pub fn get_terminal_visible_lines(&self) -> usize {
+        self.pane_rows(|areas| areas.terminal)
+    }
+    
+    // How far PageUp/PageDown move: a screenful of the focused list, less one row of overlap
+    pub fn page_size(&self) -> usize {
+        match self.active_pane {
+            ActivePane::Installed => self.get_installed_visible_items(),
+            _ => self.get_results_visible_items(),
+        }
+        .saturating_sub(1)
+        .max(1)
+    }
+    
+    // Multi-selection methods
+    pub fn toggle_package_selection(&mut self) {
+        if let Some(package) = self.get_selected_package() {
+            let package_key = format!("{}:{}", package.source, package.name);
+            if self.selected_packages.contains(&package_key) {
+                self.pinned_versions.remove(&package_key);
+                self.selected_packages.remove(&package_key);
+            } else {
+                self.selected_packages.insert(package_key);
+            }
+        }
+    }
+    
+    // Which of the package's optional dependencies `r` has picked, if it's still the highlighted one
+    pub fn related_cursor_for(&self, package: &Package) -> Option<usize> {
+        let (key, index) = self.related_cursor.as_ref()?;
+        (*key == format!("{}:{}", package.source, package.name)).then_some(*index)
+    }
+    
+    // Step through the highlighted package's optional dependencies, wrapping at the end
+    pub fn move_related_cursor(&mut self) {
+        let Some(package) = self.get_selected_package() else {
+            return;
+        };
+        if package.optional_deps.is_empty() {
+            self.set_status(format!("{} has no optional dependencies", package.name));
+            return;
+        }
+        
+        let key = format!("{}:{}", package.source, package.name);
+        let next = match self.related_cursor_for(package) {
+            Some(index) => (index + 1) % package.optional_deps.len(),
+            None => 0,
+        };
+        self.related_cursor = Some((key, next));
+    }
+    
+    // The package an optional dependency refers to: same source first, then anything providing it.
+    // Drawn for every optional dependency each frame, so it goes through the lookups
+    pub fn related_package(&self, package: &Package, dependency: &str) -> Option<&Package> {
+        let name = strip_version_constraint(dependency);
+        let named = self.packages_by_name.get(name).and_then(|indices| {
+            indices
+                .iter()
+                .find(|&&index| self.packages[index].source == package.source)
+                .or(indices.first())
+        });
+        named
+            .or_else(|| self.packages_by_provides.get(name).and_then(|indices| indices.first()))
+            .map(|&index| &self.packages[index])
+    }
+    
+    // Add or remove the optional dependency picked with `r` from the install selection
+    pub fn toggle_related_selection(&mut self) {
+        let Some(package) = self.get_selected_package() else {
+            return;
+        };
+        let Some(index) = self.related_cursor_for(package) else {
+            self.set_status("Press r to pick one of the optional dependencies first".to_string());
+            return;
+        };
+        let dependency = &package.optional_deps[index];
+        let Some(related) = self.related_package(package, dependency) else {
+            let message = format!("{} isn't in the package list", strip_version_constraint(dependency));
+            self.set_status(message);
+            return;
+        };
+        
+        let package_key = format!("{}:{}", related.source, related.name);
+        let message = if self.selected_packages.contains(&package_key) {
+            format!("Deselected {}", related.name)
+        } else {
+            format!("Selected {} (optional for {})", related.name, package.name)
+        };
+        if self.selected_packages.remove(&package_key) {
+            self.pinned_versions.remove(&package_key);
+        } else {
+            self.selected_packages.insert(package_key);
+        }
+        self.set_status(message);
+    }
+    
+    pub fn is_package_selected(&self, package: &Package) -> bool {
+        let package_key = format!("{}:{}", package.source, package.name);
+        self.selected_packages.contains(&package_key)
+    }
+    
+    // Selection keys of the filtered results, leaving out header rows
+    fn visible_package_keys(&self) -> Vec<String> {
+        self.filtered_indices
+            .iter()
+            .enumerate()
+            .filter(|(row, _)| !self.is_header_row(*row))
+            .map(|(_, &index)| format!("{}:{}", self.packages[index].source, self.packages[index].name))
+            .collect()
+    }
+    
+    pub fn select_all_visible(&mut self) {
+        for package_key in self.visible_package_keys() {
+            self.selected_packages.insert(package_key);
+        }
+    }
+
+    pub fn invert_selection(&mut self) {
+        for package_key in self.visible_package_keys() {
+            if !self.selected_packages.remove(&package_key) {
+                self.selected_packages.insert(package_key);
+            }
+        }
+        self.pinned_versions.retain(|package_key, _| self.selected_packages.contains(package_key));
+    }
+
+    // Remove the entry under the review cursor from the selection
+    pub fn remove_selection_at_cursor(&mut self) {
+        let selected = self.get_selected_packages_list();
+        if let Some(package_key) = selected.get(self.selection_cursor) {
+            self.selected_packages.remove(package_key);
+            self.pinned_versions.remove(package_key);
+        }
+        self.selection_cursor = self.selection_cursor.min(self.selected_packages.len().saturating_sub(1));
+    }
+    
+    pub fn clear_selection(&mut self) {
+        self.selected_packages.clear();
+        self.pinned_versions.clear();
+        self.selection_cursor = 0;
+    }
+    
+    // Step the highlighted package's pinned version through the versions pmux knows of,
+    // newest first, then back to unpinned. Pinning also selects the package
+    pub fn cycle_pinned_version(&mut self) {
+        let Some(package) = self.get_selected_package() else {
+            return;
+        };
+        let (source, name) = (package.source.clone(), package.name.clone());
+        let package_key = format!("{}:{}", source, name);
+        
+        if self.version_install_format(&source).is_none() {
+            self.set_status(format!("{} can't install a specific version", source));
+            return;
+        }
+        
+        let mut versions: Vec<String> = self.packages
+            .iter()
+            .chain(&self.installed_packages)
+            .filter(|p| p.source == source && p.name == name)
+            .filter_map(|p| p.version.clone())
+            .collect();
+        versions.sort_by(|a, b| compare_versions(b, a));
+        versions.dedup();
+        if versions.is_empty() {
+            self.set_status(format!("No known versions of {}", name));
+            return;
+        }
+        
+        let next = match self.pinned_versions.get(&package_key) {
+            None => Some(0),
+            Some(current) => versions
+                .iter()
+                .position(|version| version == current)
+                .map(|position| position + 1)
+                .filter(|&position| position < versions.len()),
+        };
+        match next {
+            Some(position) => {
+                let version = versions[position].clone();
+                self.set_status(format!("Pinned {} to {} ({}/{})", name, version, position + 1, versions.len()));
+                self.selected_packages.insert(package_key.clone());
+                self.pinned_versions.insert(package_key, version);
+            }
+            None => {
+                self.pinned_versions.remove(&package_key);
+                self.set_status(format!("Unpinned {}", name));
+            }
+        }
+    }
+    
+    // A manager's configured pinned-install format, or the built-in one for managers
+    // whose install command pmux knows
+    fn version_install_format(&self, source: &str) -> Option<String> {
+        if let Some(format) = self.pm_registry.get_manager(source).and_then(|m| m.version_install_format.clone()) {
+            return Some(format);
+        }
+        match source {
+            "apt" => Some("{name}={version}".to_string()),
+            "emerge" => Some("={name}-{version}".to_string()),
+            "dnf" => Some("{name}-{version}".to_string()),
+            _ => None,
+        }
+    }
+    
+    // A manager's configured downgrade command with the privilege prefix, or the built-in one
+    fn downgrade_command(&self, source: &str, spec: &str) -> Option<String> {
+        if let Some(manager) = self.pm_registry.get_manager(source).filter(|m| m.downgrade_cmd.is_some()) {
+            return self.pm_registry.get_downgrade_command(manager, spec, &self.sudoers);
+        }
+        let sudo = &self.sudoers;
+        match source {
+            "apt" => Some(format!("{} apt install --allow-downgrades {}", sudo, spec)),
+            "dnf" => Some(format!("{} dnf downgrade {}", sudo, spec)),
+            _ => None,
+        }
+    }
+    
+    // Offer the versions the highlighted installed package can go back to, newest first.
+    // pacman installs them from its package cache (or hands over to the `downgrade` helper,
+    // which can fetch from the Arch Linux Archive); other managers use the versions pmux knows of
+    pub fn open_downgrade_picker(&mut self) {
+        let Some(package) = self.highlighted_installed() else {
+            return;
+        };
+        let older = |version: &str| {
+            package.version.as_deref().is_none_or(|installed| compare_versions(version, installed) == Ordering::Less)
+        };
+        
+        let mut options = Vec::new();
+        if package.source == "pacman" {
+            let mut cached = cached_versions(Path::new(PACKAGE_CACHE_DIR), &package.name);
+            cached.retain(|(version, _)| older(version));
+            cached.sort_by(|(a, _), (b, _)| compare_versions(b, a));
+            for (version, path) in cached {
+                options.push(DowngradeOption {
+                    command: format!("{} pacman -U {}", self.sudoers, path.display()),
+                    label: format!("{} (cached)", version),
+                });
+            }
+            if binary_in_path("downgrade") {
+                options.push(DowngradeOption {
+                    label: "Pick a version with the downgrade helper".to_string(),
+                    command: format!("{} downgrade {}", self.sudoers, package.name),
+                });
+            }
+        } else if let Some(format) = self.version_install_format(&package.source) {
+            let mut versions: Vec<&str> = self.packages
+                .iter()
+                .filter(|p| p.source == package.source && p.name == package.name)
+                .filter_map(|p| p.version.as_deref())
+                .filter(|version| older(version))
+                .collect();
+            versions.sort_by(|a, b| compare_versions(b, a));
+            versions.dedup();
+            for version in versions {
+                let spec = format.replace("{name}", &package.name).replace("{version}", version);
+                if let Some(command) = self.downgrade_command(&package.source, &spec) {
+                    options.push(DowngradeOption { label: version.to_string(), command });
+                }
+            }
+        }
+        
+        if options.is_empty() {
+            let message = format!("No older version of {} to downgrade to", package.name);
+            self.set_status(message);
+            return;
+        }
+        self.downgrade_picker = Some(DowngradePicker {
+            package: package.name.clone(),
+            installed_version: package.version.clone(),
+            options,
+            cursor: 0,
+        });
+    }
+    
+    pub fn move_downgrade_cursor(&mut self, down: bool) {
+        if let Some(picker) = &mut self.downgrade_picker {
+            picker.cursor = if down {
+                (picker.cursor + 1).min(picker.options.len().saturating_sub(1))
+            } else {
+                picker.cursor.saturating_sub(1)
+            };
+        }
+    }
+    
+    // Run the picked downgrade, through the usual confirmation since it needs root
+    pub fn confirm_downgrade(&mut self) {
+        let Some(picker) = self.downgrade_picker.take() else {
+            return;
+        };
+        let Some(option) = picker.options.into_iter().nth(picker.cursor) else {
+            return;
+        };
+        if self.read_only {
+            self.set_status(format!("Read-only mode, not running: {}", option.command));
+            return;
+        }
+        
+        self.set_status(format!("Downgrading {} to {}", picker.package, option.label));
+        if self.needs_root(&option.command) {
+            self.confirm_commands = vec![option.command];
+        } else {
+            self.pending_commands.push(option.command);
+        }
+    }
+    
+    // A manager's configured dry-run command, or the built-in one for managers whose
+    // install command pmux knows
+    fn preview_format(&self, source: &str) -> Option<String> {
+        if let Some(format) = self.pm_registry.get_manager(source).and_then(|m| m.preview_cmd.clone()) {
+            return Some(format);
+        }
+        match source {
+            "emerge" => Some("emerge --pretend {}".to_string()),
+            "apt" => Some("apt-get -s install {}".to_string()),
+            "dnf" => Some("dnf install --assumeno {}".to_string()),
+            _ => None,
+        }
+    }
+    
+    // Dry runs of the selection's install commands, for the managers that have one
+    pub fn preview_commands(&self) -> Vec<String> {
+        let skipped = self.skipped_installs();
+        let keys = self.selected_packages.iter().filter(|key| !skipped.contains(key));
+        self.commands_by_source(keys, false, |source, _, names| {
+            let format = self.preview_format(source)?;
+            let packages: Vec<String> = names.iter().map(|name| self.install_spec(source, name)).collect();
+            Some(format.replace("{}", &packages.join(" ")))
+        })
+        .into_iter()
+        .map(|(command, _)| command)
+        .collect()
+    }
+    
+    // Show what installing the selection would pull in, without installing anything.
+    // Asking again goes back to the selection
+    pub fn preview_installation(&mut self) {
+        if self.showing_preview {
+            self.showing_preview = false;
+            self.terminal_scroll = 0;
+            return;
+        }
+        if self.selected_packages.is_empty() {
+            self.set_status("Nothing selected to preview".to_string());
+            return;
+        }
+        
+        let commands = self.preview_commands();
+        if commands.is_empty() {
+            self.set_status("None of the selected packages' managers has a preview_cmd".to_string());
+            return;
+        }
+        self.set_status("Previewing the install in the Terminal pane, p again to go back".to_string());
+        self.start_previews(commands);
+        self.showing_preview = true;
+    }
+    
+    fn start_previews(&mut self, commands: Vec<String>) {
+        self.preview_output.clear();
+        self.previews_running = commands.len();
+        self.pending_previews.extend(commands);
+    }
+    
+    // Kept in terminal_output too, so the dry run is still there once it's out of view
+    pub fn push_preview_output(&mut self, command: &str, output: Result<String, String>) {
+        self.previews_running = self.previews_running.saturating_sub(1);
+        let mut lines = vec![format!("$ {} (preview, nothing is installed)", command)];
+        match output {
+            Ok(text) => lines.extend(text.lines().map(str::to_string)),
+            Err(e) => lines.push(format!("Preview failed: {}", e)),
+        }
+        for line in &lines {
+            self.push_terminal_output(line.clone());
+        }
+        self.preview_output.extend(lines);
+    }
+    
+    // What the Terminal pane scrolls through when it isn't showing the selection
+    pub fn terminal_lines(&self) -> &[String] {
+        if self.showing_preview {
+            &self.preview_output
+        } else {
+            &self.terminal_output
+        }
+    }
+    
+    // How a selected package is written in its install command: pinned if it has a version set
+    fn install_spec(&self, source: &str, name: &str) -> String {
+        let pinned = self.pinned_versions.get(&format!("{}:{}", source, name));
+        match (pinned, self.version_install_format(source)) {
+            (Some(version), Some(format)) => format.replace("{name}", name).replace("{version}", version),
+            _ => name.to_string(),
+        }
+    }
+    
+    // What installing the selection downloads, counting the packages whose size is known.
+    // Drawn every frame, so match against borrowed (source, name) pairs instead of building keys
+    pub fn selection_download_size(&self) -> Option<u64> {
+        let selected: HashSet<(&str, &str)> = self.selected_packages
+            .iter()
+            .filter_map(|package_key| package_key.split_once(':'))
+            .collect();
+        sum_sizes(
+            self.packages
+                .iter()
+                .filter(|p| !p.installed && selected.contains(&(p.source.as_str(), p.name.as_str())))
+                .map(|p| p.download_size),
+        )
+    }
+    
+    pub fn get_selected_count(&self) -> usize {
+        self.selected_packages.len()
+    }
+    
+    // Replace the highlighted pacman group with its member packages in the selection,
+    // the same set `pacman -S <group>` would offer. The main loop looks the members up
+    // in the background and hands them to add_group_members
+    pub fn expand_selected_group(&mut self) {
+        let group = match self.get_selected_package() {
+            Some(package) if package.is_group => package.name.clone(),
+            _ => return,
+        };
+        
+        self.set_status(format!("Expanding group {}…", group));
+        self.pending_group_expansions.push(group);
+    }
+    
+    // `pacman -Sg <group>` finished; each line of its output is "<group> <member>"
+    pub fn add_group_members(&mut self, group: &str, output: Result<String, String>) {
+        match output {
+            Ok(stdout) => {
+                let mut count = 0;
+                for member in stdout.lines().filter_map(|line| line.split_whitespace().nth(1)) {
+                    self.selected_packages.insert(format!("pacman:{}", member));
+                    count += 1;
+                }
+                self.selected_packages.remove(&format!("pacman:{}", group));
+                self.set_status(format!("Selected {} packages from {}", count, group));
+            }
+            Err(e) => self.push_terminal_output(format!("Expanding group {} failed: {}", group, e)),
+        }
+    }
+    
+    // Pin the two selected packages side by side, or unpin them if already comparing
+    pub fn toggle_comparison(&mut self) {
+        if self.compare_packages.take().is_some() || self.selected_packages.len() != 2 {
+            return;
+        }
+        
+        let mut pinned = self.get_selected_packages_list().into_iter().filter_map(|key| {
+            self.packages
+                .iter()
+                .chain(self.installed_packages.iter())
+                .find(|p| format!("{}:{}", p.source, p.name) == key)
+                .cloned()
+        });
+        
+        if let (Some(left), Some(right)) = (pinned.next(), pinned.next()) {
+            self.compare_packages = Some((left, right));
+        }
+    }
+    
+    // Selected packages ("source:name") that conflict with something already installed,
+    // with the installed names ("source:name" when another manager installed them). Either
+    // side may declare the conflict, so check both ways. Drawn every frame, so it's only
+    // worked out again once the selection or the package lists change
+    pub fn selection_conflicts(&self) -> Ref<'_, BTreeMap<String, Vec<String>>> {
+        let stale = self.conflicts.borrow().as_ref().is_none_or(|(generation, selection, _)| {
+            *generation != self.packages_generation || *selection != self.selected_packages
+        });
+        if stale {
+            let conflicts = self.find_selection_conflicts();
+            *self.conflicts.borrow_mut() = Some((self.packages_generation, self.selected_packages.clone(), conflicts));
+        }
+        Ref::map(self.conflicts.borrow(), |cache| &cache.as_ref().expect("filled in above").2)
+    }
+    
+    // Conflicts are matched by name and provides across every manager, since an AUR
+    // package can conflict with one pacman installed
+    fn find_selection_conflicts(&self) -> BTreeMap<String, Vec<String>> {
+        let mut conflicts = BTreeMap::new();
+        for package_key in &self.selected_packages {
+            let Some((source, name)) = package_key.split_once(':') else {
+                continue;
+            };
+            let Some(package) = self.packages.iter().find(|p| p.source == source && p.name == name) else {
+                continue;
+            };
+            
+            let installed: Vec<String> = self.installed_packages
+                .iter()
+                .filter(|installed| installed.name != package.name)
+                .filter(|installed| {
+                    package.conflicts.iter().any(|c| *c == installed.name || installed.provides.contains(c)) ||
+                    installed.conflicts.iter().any(|c| *c == package.name || package.provides.contains(c))
+                })
+                .map(|installed| if installed.source == package.source {
+                    installed.name.clone()
+                } else {
+                    format!("{}:{}", installed.source, installed.name)
+                })
+                .collect();
+            if !installed.is_empty() {
+                conflicts.insert(package_key.clone(), installed);
+            }
+        }
+        conflicts
+    }
+    
+    // Selected AUR packages flagged out-of-date, with how many days ago they were flagged
+    pub fn selection_out_of_date(&self) -> Vec<(String, u64)> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut flagged: Vec<(String, u64)> = self.selected_packages
+            .iter()
+            .filter_map(|package_key| {
+                let (source, name) = package_key.split_once(':')?;
+                let package = self.packages.iter().find(|p| p.source == source && p.name == name)?;
+                let flagged_at = package.out_of_date?;
+                Some((package_key.clone(), now.saturating_sub(flagged_at) / 86_400))
+            })
+            .collect();
+        flagged.sort();
+        flagged
+    }
+    
+    pub fn get_selected_packages_list(&self) -> Vec<String> {
+        // Sorted so the review list keeps a stable order while it's being edited
+        let mut selected: Vec<String> = self.selected_packages.iter().cloned().collect();
+        selected.sort();
+        selected
+    }
+    
+    // Open the highlighted package's upstream URL in the browser
+    pub fn open_selected_url(&self) {
+        let package = match self.active_pane {
+            ActivePane::Installed => self.highlighted_installed(),
+            _ => self.get_selected_package(),
+        };
+        
+        if let Some(url) = package.and_then(|p| p.url.as_ref()) {
+            let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+            // Detach from the TUI so the opener can't write over the alternate screen. Some
+            // openers only exit once the browser does, so it's reaped off the UI thread
+            let child = std::process::Command::new(opener)
+                .arg(url)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn();
+            if let Ok(mut child) = child {
+                std::thread::spawn(move || child.wait());
+            }
+        }
+    }
+    
+    // Async search method for AUR integration
+    pub async fn search_aur_packages(&mut self, query: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Search AUR packages using the AUR client
+        let aur_results = self.aur_client.search(query).await?;
+        
+        // Add AUR results to the packages list
+        for aur_package in aur_results {
             // Check if package already exists (avoid duplicates)
             if !self.packages.iter().any(|p| p.name == aur_package.name && p.source == aur_package.source) {
                 self.packages.push(aur_package);
             }
         }
+        self.index_packages();
         
         // Re-filter packages with current search
         self.filter_packages();
@@ -342,6 +2018,9 @@ impl App {
             if let Some(description) = &package.description {
                 details.push_str(&format!("Description: {}\n", description));
             }
+            if let Some(url) = &package.url {
+                details.push_str(&format!("URL: {}\n", url));
+            }
             details.push_str(&format!("Source: {}\n", package.source));
             details.push_str(&format!("Installed: {}\n", if package.installed { "Yes" } else { "No" }));
             details
@@ -356,50 +2035,682 @@ impl App {
                 self.packages.push(aur_package);
             }
         }
+        self.index_packages();
         
         // Re-filter packages with current search
         self.filter_packages();
     }
     
+    // Append to the Terminal pane, dropping the oldest lines past the configured limit
+    pub fn push_terminal_output(&mut self, line: String) {
+        self.terminal_output.push(line);
+        if self.terminal_output.len() > self.terminal_max_lines {
+            let excess = self.terminal_output.len() - self.terminal_max_lines;
+            self.terminal_output.drain(..excess);
+        }
+        self.terminal_scroll = 0;
+    }
+    
+    pub fn clear_terminal_output(&mut self) {
+        self.terminal_output.clear();
+        self.terminal_scroll = 0;
+    }
+    
+    // One command per package manager for the current selection, in a stable order.
+    // Built-in managers keep their known-good commands; TOML-defined ones use their install_cmd
+    pub fn get_install_commands(&self) -> Vec<String> {
+        self.install_batch().into_iter().map(|(command, _)| command).collect()
+    }
+    
+    fn install_batch(&self) -> Vec<(String, Vec<String>)> {
+        let skipped = self.skipped_installs();
+        let keys = self.selected_packages.iter().filter(|key| !skipped.contains(key));
+        let skip_installed = self.config.pm.skip_installed;
+        let per_package = self.config.pm.install_batching == InstallBatching::PerPackage;
+        let sudo = &self.sudoers;
+        self.commands_by_source(keys, per_package, |source, _, names| {
+            let packages: Vec<String> = names.iter().map(|name| self.install_spec(source, name)).collect();
+            let package_list = packages.join(" ");
+            Some(match source {
+                "pacman" if skip_installed => format!("{} pacman -S --needed {}", sudo, package_list),
+                "pacman" => format!("{} pacman -S {}", sudo, package_list),
+                "paru" | "aur" if skip_installed => format!("paru -S --needed {}", package_list),
+                "paru" | "aur" => format!("paru -S {}", package_list),
+                "dnf" => format!("{} dnf install {}", sudo, package_list),
+                "emerge" if skip_installed => format!("{} emerge --noreplace {}", sudo, package_list),
+                "emerge" => format!("{} emerge {}", sudo, package_list),
+                "nix" => format!("nix-env -iA {}", package_list),
+                "apt" => format!("{} apt install {}", sudo, package_list),
+                "pkg" => format!("{} pkg install {}", sudo, package_list),
+                // OpenWrt has no sudo; opkg is run as root already
+                "opkg" => format!("opkg install {}", package_list),
+                "pkgin" => format!("{} pkgin install {}", sudo, package_list),
+                // winget installs one package per invocation
+                "winget" => names
+                    .iter()
+                    .map(|name| format!("winget.exe install --exact --id {}", name))
+                    .collect::<Vec<_>>()
+                    .join(" && "),
+                _ => {
+                    let manager = self.pm_registry.get_manager(source)?;
+                    self.pm_registry.get_install_command(manager, &packages, sudo)
+                }
+            })
+        })
+    }
+    
+    // With [pm] skip_installed, the selected packages that installing would only reinstall.
+    // pacman, paru and emerge skip those themselves via --needed/--noreplace, and a
+    // package pinned to a version is installed at that version regardless
+    pub fn skipped_installs(&self) -> Vec<&String> {
+        if !self.config.pm.skip_installed {
+            return Vec::new();
+        }
+        
+        let installed: HashSet<(&str, &str)> = self.installed_packages
+            .iter()
+            .map(|p| (p.source.as_str(), p.name.as_str()))
+            .collect();
+        let mut skipped: Vec<&String> = self.selected_packages
+            .iter()
+            .filter(|key| {
+                let Some((source, name)) = key.split_once(':') else {
+                    return false;
+                };
+                !matches!(source, "pacman" | "paru" | "aur" | "emerge")
+                    && !self.pinned_versions.contains_key(*key)
+                    && installed.contains(&(source, name))
+            })
+            .collect();
+        skipped.sort();
+        skipped
+    }
+    
+    // Commands run through the sudoers program wait for a yes/no, since it may ask for a password
+    pub fn needs_root(&self, command: &str) -> bool {
+        command.strip_prefix(self.sudoers.as_str()).is_some_and(|rest| rest.starts_with(' '))
+    }
+    
+    // Counterpart of install_batch(), for undoing the last install
+    fn removal_batch(&self, package_keys: &[String]) -> Vec<(String, Vec<String>)> {
+        let sudo = &self.sudoers;
+        self.commands_by_source(package_keys, false, |source, package_list, packages| {
+            Some(match source {
+                "pacman" => format!("{} pacman -R {}", sudo, package_list),
+                "paru" | "aur" => format!("paru -R {}", package_list),
+                "dnf" => format!("{} dnf remove {}", sudo, package_list),
+                "emerge" => format!("{} emerge --depclean {}", sudo, package_list),
+                "nix" => format!("nix-env -e {}", package_list),
+                "apt" => format!("{} apt remove {}", sudo, package_list),
+                "pkg" => format!("{} pkg delete {}", sudo, package_list),
+                "opkg" => format!("opkg remove {}", package_list),
+                "pkgin" => format!("{} pkgin remove {}", sudo, package_list),
+                "winget" => packages
+                    .iter()
+                    .map(|name| format!("winget.exe uninstall --exact --id {}", name))
+                    .collect::<Vec<_>>()
+                    .join(" && "),
+                _ => {
+                    let manager = self.pm_registry.get_manager(source)?;
+                    self.pm_registry.get_remove_command(manager, packages, sudo)?
+                }
+            })
+        })
+    }
+    
+    // Group "source:name" keys by source and build one command per source, or one per
+    // package with `per_package`, paired with the keys it covers. Sources the builder has
+    // no command for are skipped
+    fn commands_by_source<'a>(
+        &self,
+        package_keys: impl IntoIterator<Item = &'a String>,
+        per_package: bool,
+        build: impl Fn(&str, &str, &[String]) -> Option<String>,
+    ) -> Vec<(String, Vec<String>)> {
+        let mut by_source: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for package_key in package_keys {
+            if let Some((source, package_name)) = package_key.split_once(':') {
+                by_source.entry(source).or_default().push(package_name.to_string());
+            }
+        }
+        
+        let mut batch = Vec::new();
+        for (source, mut packages) in by_source {
+            packages.sort();
+            let groups: Vec<Vec<String>> = if per_package {
+                packages.into_iter().map(|name| vec![name]).collect()
+            } else {
+                vec![packages]
+            };
+            for packages in groups {
+                if let Some(command) = build(source, &packages.join(" "), &packages) {
+                    let keys = packages.iter().map(|name| format!("{}:{}", source, name)).collect();
+                    batch.push((command, keys));
+                }
+            }
+        }
+        
+        batch
+    }
+    
+    // Copy the install commands so they can be run by hand, e.g. from a root shell
+    pub fn copy_install_commands(&mut self) {
+        let commands = self.get_install_commands();
+        if commands.is_empty() {
+            self.set_status("Nothing selected to copy".to_string());
+            return;
+        }
+        
+        if self.copy_to_clipboard(commands.join("\n")) {
+            self.set_status(format!("Copied {} install command(s) to the clipboard", commands.len()));
+        } else {
+            self.set_status("Clipboard is not available".to_string());
+        }
+    }
+    
+    // A standalone sh script running the selection's install commands
+    pub fn install_script(&self) -> Option<String> {
+        let commands = self.get_install_commands();
+        if commands.is_empty() {
+            return None;
+        }
+        
+        let mut script = String::from("#!/bin/sh\n# Generated by pmux\nset -e\n\n");
+        for command in commands {
+            script.push_str(&format!("{}\n", command));
+        }
+        Some(script)
+    }
+    
+    // Copy the install script, or write it to the cache dir when there's no clipboard
+    // (a bare TTY or an ssh session)
+    pub fn export_install_script(&mut self) {
+        let Some(script) = self.install_script() else {
+            self.set_status("Nothing selected to export".to_string());
+            return;
+        };
+        
+        if self.copy_to_clipboard(script.clone()) {
+            self.set_status("Copied the install script to the clipboard".to_string());
+            return;
+        }
+        match Cache::save_install_script(&script) {
+            Ok(path) => self.set_status(format!("Clipboard is not available, wrote the install script to {}", path.display())),
+            Err(e) => self.set_status(format!("Failed to write the install script: {}", e)),
+        }
+    }
+    
+    fn copy_to_clipboard(&mut self, text: String) -> bool {
+        // On X11 the clipboard is served by the process that set it, so keep the handle alive
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        self.clipboard
+            .as_mut()
+            .is_some_and(|clipboard| clipboard.set_text(text).is_ok())
+    }
+    
+    pub fn set_status(&mut self, message: String) {
+        self.status_message = Some((message, Instant::now()));
+    }
+    
+    // Status messages fade out after a few seconds
+    pub fn current_status(&self) -> Option<&str> {
+        self.status_message
+            .as_ref()
+            .filter(|(_, set_at)| set_at.elapsed() < Duration::from_secs(4))
+            .map(|(message, _)| message.as_str())
+    }
+    
+    // Whether a "source:name" key was already on the system before it was installed
+    fn was_installed(&self, package_key: &str) -> bool {
+        let Some((source, name)) = package_key.split_once(':') else {
+            return false;
+        };
+        self.packages.iter().any(|p| p.source == source && p.name == name && p.installed)
+            || self.installed_packages.iter().any(|p| p.source == source && p.name == name)
+    }
+    
     // Start installation of selected packages
     pub fn start_installation(&mut self) {
         if self.selected_packages.is_empty() {
             return;
         }
         
-        // Group packages by source/package manager
-        let mut install_commands = std::collections::HashMap::new();
-        
-        for package_key in &self.selected_packages {
-            let parts: Vec<&str> = package_key.split(':').collect();
-            if parts.len() == 2 {
-                let source = parts[0];
-                let package_name = parts[1];
-                
-                install_commands.entry(source.to_string())
-                    .or_insert_with(Vec::new)
-                    .push(package_name.to_string());
-            }
+        let batch = self.install_batch();
+        let commands: Vec<String> = batch.iter().map(|(command, _)| command.clone()).collect();
+        let skipped: Vec<String> = self.skipped_installs().into_iter().cloned().collect();
+        if commands.is_empty() && !skipped.is_empty() {
+            self.set_status("Everything selected is already installed".to_string());
+            return;
         }
-        
-        // Execute installation commands
-        for (source, packages) in install_commands {
-            let package_list = packages.join(" ");
-            let command = match source.as_str() {
-                "pacman" => format!("sudo pacman -S {}", package_list),
-                "paru" => format!("paru -S {}", package_list),
-                "dnf" => format!("sudo dnf install {}", package_list),
-                "emerge" => format!("sudo emerge {}", package_list),
-                "nix" => format!("nix-env -iA {}", package_list),
-                "apt" => format!("sudo apt install {}", package_list),
-                _ => continue,
-            };
-            
-            // For now, just print the command (in a real implementation, this would execute)
-            println!("Would execute: {}", command);
+        if self.read_only {
+            self.set_status(format!("Read-only mode, not running: {}", commands.join("; ")));
+            return;
         }
+        if !skipped.is_empty() {
+            self.push_terminal_output(format!("Skipping already installed: {}", skipped.join(", ")));
+        }
+        // Undo only removes what this install adds, not packages it reinstalls or upgrades
+        let batch: Vec<(String, Vec<String>)> = batch
+            .into_iter()
+            .map(|(command, keys)| {
+                let added = keys.into_iter().filter(|key| !self.was_installed(key)).collect();
+                (command, added)
+            })
+            .collect();
+        self.queued_installs.extend(batch);
+        let needs_confirming = commands.iter().any(|command| self.needs_root(command))
+            || !self.selection_conflicts().is_empty()
+            || !self.selection_out_of_date().is_empty();
+        if needs_confirming {
+            // Privileged, conflicting or out-of-date installs wait for confirm_installation(),
+            // with a dry run in the confirmation to judge them by
+            self.confirm_commands = commands;
+            self.confirming_selection = true;
+            self.start_previews(self.preview_commands());
+            return;
+        }
+        
+        self.pending_commands.extend(commands);
         
         // Clear selection after installation
         self.clear_selection();
     }
-}
\ No newline at end of file
+    
+    // Install just the highlighted result, the way Enter installs the selection, and leave
+    // the selection as it was. The package stands in as the whole selection while the
+    // install is set up (and confirmed, if it needs confirming)
+    pub fn install_highlighted(&mut self) {
+        let Some(package) = self.get_selected_package() else {
+            return;
+        };
+        let package_key = format!("{}:{}", package.source, package.name);
+        let pinned: HashMap<String, String> = self.pinned_versions
+            .get(&package_key)
+            .map(|version| HashMap::from([(package_key.clone(), version.clone())]))
+            .unwrap_or_default();
+        
+        let selection = std::mem::replace(&mut self.selected_packages, HashSet::from([package_key]));
+        let pins = std::mem::replace(&mut self.pinned_versions, pinned);
+        self.start_installation();
+        if self.confirming_selection {
+            self.stashed_selection = Some((selection, pins));
+        } else {
+            self.restore_selection((selection, pins));
+        }
+    }
+    
+    fn restore_selection(&mut self, (selection, pins): (HashSet<String>, HashMap<String, String>)) {
+        self.selected_packages = selection;
+        self.pinned_versions = pins;
+        self.selection_cursor = 0;
+    }
+    
+    pub fn confirm_installation(&mut self) {
+        self.showing_preview = false;
+        // Confirming an undo or a re-run leaves the selection alone
+        if std::mem::take(&mut self.confirming_selection) {
+            self.clear_selection();
+        }
+        if let Some(stashed) = self.stashed_selection.take() {
+            self.restore_selection(stashed);
+        }
+        self.pending_commands.append(&mut self.confirm_commands);
+    }
+    
+    pub fn cancel_installation(&mut self) {
+        self.showing_preview = false;
+        self.confirm_commands.clear();
+        self.confirming_selection = false;
+        self.queued_installs.clear();
+        self.queued_removals.clear();
+        if let Some(stashed) = self.stashed_selection.take() {
+            self.restore_selection(stashed);
+        }
+    }
+    
+    // Run the last batch again, e.g. after a network blip or a held database lock
+    pub fn rerun_last_batch(&mut self) {
+        if self.last_batch.is_empty() {
+            self.set_status("No commands to re-run yet".to_string());
+            return;
+        }
+        let commands = self.last_batch.clone();
+        if self.read_only {
+            self.set_status(format!("Read-only mode, not running: {}", commands.join("; ")));
+            return;
+        }
+        
+        self.push_terminal_output("Re-running the last batch:".to_string());
+        for command in &commands {
+            self.push_terminal_output(format!("  {}", command));
+        }
+        self.queued_installs = self.last_batch_installs.clone();
+        self.queued_removals = self.last_batch_removals.clone();
+        
+        if commands.iter().any(|command| self.needs_root(command)) {
+            self.confirm_commands = commands;
+        } else {
+            self.pending_commands.extend(commands);
+        }
+    }
+    
+    // Remove exactly the packages pmux's last install batch put on the system
+    pub fn undo_last_install(&mut self) {
+        let Some(last_install) = Cache::load_last_install() else {
+            self.set_status("No pmux install to undo".to_string());
+            return;
+        };
+        
+        let batch = self.removal_batch(&last_install.packages);
+        if batch.is_empty() {
+            self.set_status("No remove command is configured for the last install's package managers".to_string());
+            return;
+        }
+        
+        let commands: Vec<String> = batch.iter().map(|(command, _)| command.clone()).collect();
+        if self.read_only {
+            self.set_status(format!("Read-only mode, not running: {}", commands.join("; ")));
+            return;
+        }
+        self.queued_removals.extend(batch);
+        self.set_status(format!("Undoing the install from {}", format_age(last_install.timestamp)));
+        if commands.iter().any(|command| self.needs_root(command)) {
+            self.confirm_commands = commands;
+        } else {
+            self.pending_commands.extend(commands);
+        }
+    }
+    
+    pub fn is_undoing(&self) -> bool {
+        !self.queued_removals.is_empty()
+    }
+    
+    // Called once a batch has run: successful installs become the new undo record,
+    // and successfully removed packages are dropped from it
+    pub fn record_last_install(&mut self, results: &[CommandResult]) {
+        let mut installed = Vec::new();
+        let mut removed = HashSet::new();
+        for result in results.iter().filter(|result| result.succeeded()) {
+            if let Some(keys) = self.queued_installs.get(&result.command) {
+                installed.extend(keys.iter().cloned());
+            }
+            if let Some(keys) = self.queued_removals.get(&result.command) {
+                removed.extend(keys.iter().cloned());
+            }
+        }
+        self.last_batch = results.iter().map(|result| result.command.clone()).collect();
+        self.last_batch_installs = std::mem::take(&mut self.queued_installs);
+        self.last_batch_removals = std::mem::take(&mut self.queued_removals);
+        
+        let saved = if !installed.is_empty() {
+            Cache::save_last_install(&LastInstall { timestamp: unix_now(), packages: installed })
+        } else if let Some(mut last_install) = Cache::load_last_install().filter(|_| !removed.is_empty()) {
+            last_install.packages.retain(|key| !removed.contains(key));
+            if last_install.packages.is_empty() {
+                Cache::clear_last_install()
+            } else {
+                Cache::save_last_install(&last_install)
+            }
+        } else {
+            Ok(())
+        };
+        
+        if let Err(e) = saved {
+            log::warn!("Failed to update the last install record: {}", e);
+        }
+    }
+}
+
+// Name, description and provides joined by newlines, which a search query never
+// contains, so a match can't straddle two fields
+fn search_key(package: &Package) -> String {
+    let mut key = package.name.to_lowercase();
+    if let Some(description) = &package.description {
+        key.push('\n');
+        key.push_str(&description.to_lowercase());
+    }
+    for provide in &package.provides {
+        key.push('\n');
+        key.push_str(&provide.to_lowercase());
+    }
+    key
+}
+
+// Orders multi-word matches: more of the words in the name first, then the words closest
+// together, measured as the stretch of text from the first word found to the end of the last
+fn term_rank(key: &str, terms: &[&str], name_only: bool) -> (Reverse<usize>, usize) {
+    let name = key.split('\n').next().unwrap_or_default();
+    let text = if name_only { name } else { key };
+    let name_hits = terms.iter().filter(|term| name.contains(*term)).count();
+    
+    let found = terms.iter().filter_map(|term| text.find(term).map(|start| (start, start + term.len())));
+    let (start, end) = found.fold((usize::MAX, 0), |(start, end), (s, e)| (start.min(s), end.max(e)));
+    (Reverse(name_hits), end.saturating_sub(start))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// None when no size is known at all, rather than a misleading 0
+fn sum_sizes(sizes: impl Iterator<Item = Option<u64>>) -> Option<u64> {
+    sizes.flatten().fold(None, |total, size| Some(total.unwrap_or(0) + size))
+}
+
+// "45.6 GiB" style size in binary units, the way pacman reports them
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+// "5 minutes ago" style description of a unix timestamp
+fn format_age(timestamp: u64) -> String {
+    let seconds = unix_now().saturating_sub(timestamp);
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} minute(s) ago", seconds / 60),
+        3600..=86399 => format!("{} hour(s) ago", seconds / 3600),
+        _ => format!("{} day(s) ago", seconds / 86400),
+    }
+}
+
+fn common_prefix(words: &[String]) -> &str {
+    let Some(first) = words.first() else {
+        return "";
+    };
+    let mut prefix = first.as_str();
+    for word in &words[1..] {
+        while !word.starts_with(prefix) {
+            let end = prefix.char_indices().next_back().map_or(0, |(i, _)| i);
+            prefix = &prefix[..end];
+        }
+    }
+    prefix
+}
+
+// Variants that crowd out the application itself in most searches
+fn is_noise_package(name: &str) -> bool {
+    name.starts_with("lib32-") || name.ends_with("-dbg") || name.ends_with("-debug") || name.ends_with("-doc")
+}
+
+// The scroll offset that brings `index` into a window of `visible` rows with `margin` rows
+// of context on either side, moving as little as possible. Near the ends of a list of `len`
+// entries the margin gives way rather than scrolling past the first or last entry
+fn scroll_to_show(index: usize, scroll: usize, visible: usize, margin: usize, len: usize) -> usize {
+    // A margin of half the window or more would keep the cursor centred; stop short of that
+    let margin = margin.min(visible.saturating_sub(1) / 2);
+    let scroll = if index < scroll + margin {
+        index.saturating_sub(margin)
+    } else if index + margin >= scroll + visible {
+        (index + margin + 1).saturating_sub(visible)
+    } else {
+        scroll
+    };
+    scroll.min(len.saturating_sub(visible))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_follows_the_cursor_at_various_heights() {
+        for visible in [1, 5, 10, 37] {
+            let len = 100;
+            let mut scroll = 0;
+            for index in 0..len {
+                scroll = scroll_to_show(index, scroll, visible, 0, len);
+                assert!(index >= scroll && index < scroll + visible, "row {} hidden with {} visible", index, visible);
+            }
+            // Stopping on the last row, not past it
+            assert_eq!(scroll, len - visible);
+            for index in (0..len).rev() {
+                scroll = scroll_to_show(index, scroll, visible, 0, len);
+                assert!(index >= scroll && index < scroll + visible, "row {} hidden with {} visible", index, visible);
+            }
+            assert_eq!(scroll, 0);
+        }
+    }
+
+    #[test]
+    fn scroll_margin_keeps_context_but_not_past_the_ends() {
+        // Moving down into the last three rows of a ten-row window scrolls
+        assert_eq!(scroll_to_show(6, 0, 10, 3, 100), 0);
+        assert_eq!(scroll_to_show(7, 0, 10, 3, 100), 1);
+        // Moving up into the first three rows scrolls back
+        assert_eq!(scroll_to_show(23, 20, 10, 3, 100), 20);
+        assert_eq!(scroll_to_show(22, 20, 10, 3, 100), 19);
+        // The margin gives way at either end of the list
+        assert_eq!(scroll_to_show(1, 0, 10, 3, 100), 0);
+        assert_eq!(scroll_to_show(99, 85, 10, 3, 100), 90);
+        // A margin bigger than half the window is capped
+        assert_eq!(scroll_to_show(50, 40, 5, 10, 100), 48);
+        // Lists shorter than the window never scroll
+        assert_eq!(scroll_to_show(3, 0, 10, 3, 4), 0);
+    }
+
+    #[test]
+    fn visible_items_match_the_drawn_panes() {
+        let mut app = App::default();
+        // Status bar, search field (3) and details (8) come off the left column's height;
+        // the right column splits what's left of the screen in half. Borders take two rows
+        for (height, results, installed) in [(24, 10, 10), (40, 26, 18), (60, 46, 28)] {
+            app.terminal_size = (120, height);
+            assert_eq!(app.get_results_visible_items(), results, "results at height {}", height);
+            assert_eq!(app.get_installed_visible_items(), installed, "installed at height {}", height);
+        }
+    }
+
+    #[test]
+    fn root_commands_use_the_configured_sudoers() {
+        let mut app = App::default();
+        app.sudoers = "doas".to_string();
+        app.selected_packages.insert("pacman:vim".to_string());
+
+        let commands = app.get_install_commands();
+        assert_eq!(commands, vec!["doas pacman -S vim"]);
+        assert!(app.needs_root(&commands[0]));
+        assert!(!app.needs_root("sudo pacman -S vim"));
+        assert!(!app.needs_root("doasx pacman -S vim"));
+    }
+
+    #[test]
+    fn undo_record_leaves_out_already_installed_packages() {
+        let package = |name: &str, installed: bool| Package {
+            name: name.to_string(),
+            installed,
+            source: "pacman".to_string(),
+            ..Default::default()
+        };
+        let mut app = App::default();
+        app.set_packages(vec![package("vim", true), package("nano", false)]);
+        app.selected_packages.insert("pacman:vim".to_string());
+        app.selected_packages.insert("pacman:nano".to_string());
+
+        app.start_installation();
+        let queued: Vec<&Vec<String>> = app.queued_installs.values().collect();
+        assert_eq!(queued, vec![&vec!["pacman:nano".to_string()]]);
+    }
+
+    #[test]
+    fn related_package_prefers_the_same_source_then_providers() {
+        let package = |name: &str, source: &str, provides: &[&str]| Package {
+            name: name.to_string(),
+            source: source.to_string(),
+            provides: provides.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut app = App::default();
+        app.set_packages(vec![
+            package("python", "apt", &[]),
+            package("python", "pacman", &[]),
+            package("pulseaudio", "pacman", &["pulse-native-provider"]),
+        ]);
+        let owner = package("mpd", "pacman", &[]);
+
+        let related = |dependency| app.related_package(&owner, dependency).map(|p| (p.name.as_str(), p.source.as_str()));
+        assert_eq!(related("python>=3: scripting"), Some(("python", "pacman")));
+        assert_eq!(related("pulse-native-provider: audio output"), Some(("pulseaudio", "pacman")));
+        assert_eq!(related("jack: audio output"), None);
+    }
+
+    #[test]
+    fn updates_are_counted_again_on_every_load() {
+        let package = |version: &str, installed: bool| Package {
+            name: "vim".to_string(),
+            version: Some(version.to_string()),
+            installed,
+            source: "pacman".to_string(),
+            ..Default::default()
+        };
+        let mut app = App::default();
+        app.config.ui.check_updates_on_start = true;
+        app.set_packages(vec![package("9.0-1", true), package("9.1-1", false)]);
+        assert_eq!(app.updates_available, Some(1));
+
+        app.set_packages(vec![package("9.1-1", true), package("9.1-1", false)]);
+        assert_eq!(app.updates_available, Some(0));
+    }
+
+    #[test]
+    fn conflicts_are_found_across_sources() {
+        let mut app = App::default();
+        app.set_packages(vec![Package {
+            name: "vim-git".to_string(),
+            source: "aur".to_string(),
+            provides: vec!["vim".to_string()],
+            conflicts: vec!["vim".to_string()],
+            ..Default::default()
+        }]);
+        app.set_installed_packages(vec![Package {
+            name: "vim".to_string(),
+            installed: true,
+            source: "pacman".to_string(),
+            ..Default::default()
+        }]);
+        assert!(app.selection_conflicts().is_empty());
+
+        app.selected_packages.insert("aur:vim-git".to_string());
+        let expected = BTreeMap::from([("aur:vim-git".to_string(), vec!["pacman:vim".to_string()])]);
+        assert_eq!(*app.selection_conflicts(), expected);
+
+        // Replacing the installed list works the conflicts out again
+        app.set_installed_packages(Vec::new());
+        assert!(app.selection_conflicts().is_empty());
+    }
+}
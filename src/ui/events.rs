@@ -1,7 +1,79 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use crate::ui::app::{App, ActivePane, InputMode};
 
+// Bracketed paste delivers the whole paste as one event
+pub fn handle_paste_event(app: &mut App, text: &str) {
+    // Popups are modal for pastes as well as keys
+    if !app.install_summary.is_empty()
+        || !app.confirm_commands.is_empty()
+        || app.managers_overview.is_some()
+        || app.configs_overview
+        || app.downgrade_picker.is_some()
+    {
+        return;
+    }
+    
+    app.paste_text(text);
+}
+
 pub fn handle_key_event(app: &mut App, key: KeyEvent) {
+    // The post-install summary is modal: continue with another batch or quit
+    if !app.install_summary.is_empty() {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('c') => app.install_summary.clear(),
+            KeyCode::Char('q') => app.quit(),
+            _ => {}
+        }
+        return;
+    }
+    
+    // The install confirmation is modal
+    if !app.confirm_commands.is_empty() {
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('y') => app.confirm_installation(),
+            KeyCode::Esc | KeyCode::Char('n') => app.cancel_installation(),
+            _ => {}
+        }
+        return;
+    }
+    
+    if app.managers_overview.is_some() {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('m') => app.managers_overview = None,
+            KeyCode::Up | KeyCode::Char('k') => app.move_managers_cursor(false),
+            KeyCode::Down | KeyCode::Char('j') => app.move_managers_cursor(true),
+            KeyCode::Char(' ') => app.toggle_manager_at_cursor(),
+            KeyCode::Char('s') => app.save_disabled_managers(),
+            KeyCode::Char('q') => app.quit(),
+            _ => {}
+        }
+        return;
+    }
+    
+    if app.configs_overview {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('P') => app.configs_overview = false,
+            KeyCode::Up | KeyCode::Char('k') => app.move_configs_cursor(false),
+            KeyCode::Down | KeyCode::Char('j') => app.move_configs_cursor(true),
+            KeyCode::Char('r') => app.reload_managers = true,
+            KeyCode::Char('q') => app.quit(),
+            _ => {}
+        }
+        return;
+    }
+    
+    if app.downgrade_picker.is_some() {
+        match key.code {
+            KeyCode::Enter => app.confirm_downgrade(),
+            KeyCode::Esc | KeyCode::Char('D') => app.downgrade_picker = None,
+            KeyCode::Up | KeyCode::Char('k') => app.move_downgrade_cursor(false),
+            KeyCode::Down | KeyCode::Char('j') => app.move_downgrade_cursor(true),
+            KeyCode::Char('q') => app.quit(),
+            _ => {}
+        }
+        return;
+    }
+    
     match app.input_mode {
         InputMode::Normal => handle_normal_mode(app, key),
         InputMode::Editing => handle_editing_mode(app, key),
@@ -9,7 +81,18 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) {
 }
 
 fn handle_normal_mode(app: &mut App, key: KeyEvent) {
+    // While a jump-to-name is in progress every letter extends it, even bound ones
+    if let Some(c) = typeahead_char(app, key).filter(|_| app.typeahead_active()) {
+        app.jump_to_typed(c);
+        return;
+    }
+    
     match key.code {
+        // Close the comparison view before quitting
+        KeyCode::Esc if app.compare_packages.is_some() => {
+            app.compare_packages = None;
+        }
+        
         // Quit
         KeyCode::Char('q') | KeyCode::Esc => {
             app.quit();
@@ -18,7 +101,7 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         // Navigation - only works when focused on navigable panes
         KeyCode::Up | KeyCode::Char('k') => {
             match app.active_pane {
-                ActivePane::Results | ActivePane::Installed => {
+                ActivePane::Results | ActivePane::Installed | ActivePane::Terminal => {
                     app.move_up();
                 }
                 _ => {}
@@ -26,7 +109,7 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         }
         KeyCode::Down | KeyCode::Char('j') => {
             match app.active_pane {
-                ActivePane::Results | ActivePane::Installed => {
+                ActivePane::Results | ActivePane::Installed | ActivePane::Terminal => {
                     app.move_down();
                 }
                 _ => {}
@@ -34,12 +117,37 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         }
         
         // Multi-selection with Ctrl+Space (only in Results pane)
-        KeyCode::Char(' ') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            if app.active_pane == ActivePane::Results {
-                app.toggle_package_selection();
-            }
+        KeyCode::Char(' ') if key.modifiers.contains(KeyModifiers::CONTROL) && app.active_pane == ActivePane::Results => {
+            app.toggle_package_selection();
         }
         
+        // Remove the highlighted entry from the selection review (Terminal pane)
+        KeyCode::Char('d') | KeyCode::Char(' ')
+            if app.active_pane == ActivePane::Terminal && !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.remove_selection_at_cursor();
+        }
+        
+        // Clear the command output in the Terminal pane
+        KeyCode::Char('C') if app.active_pane == ActivePane::Terminal => {
+            app.clear_terminal_output();
+        }
+        
+        // Select all filtered results with Ctrl+A
+        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL)
+            && app.active_pane == ActivePane::Results => {
+            app.select_all_visible();
+        }
+
+        // Invert selection within filtered results with Ctrl+R or '*'. Not Ctrl+I: terminals
+        // send that as Tab
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL)
+            && app.active_pane == ActivePane::Results => {
+            app.invert_selection();
+        }
+        KeyCode::Char('*') if app.active_pane == ActivePane::Results => {
+            app.invert_selection();
+        }
+
         // Pane switching with Tab
         KeyCode::Tab => {
             app.switch_pane();
@@ -50,11 +158,16 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
             app.enter_search_mode();
         }
         
+        // Jump to the first entry whose name starts with the letters typed next
+        KeyCode::Char('\'') if matches!(app.active_pane, ActivePane::Results | ActivePane::Installed) => {
+            app.start_typeahead();
+        }
+        
         // Page navigation - only in navigable panes
         KeyCode::PageUp => {
             match app.active_pane {
                 ActivePane::Results | ActivePane::Installed => {
-                    for _ in 0..10 {
+                    for _ in 0..app.page_size() {
                         app.move_up();
                     }
                 }
@@ -64,7 +177,7 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         KeyCode::PageDown => {
             match app.active_pane {
                 ActivePane::Results | ActivePane::Installed => {
-                    for _ in 0..10 {
+                    for _ in 0..app.page_size() {
                         app.move_down();
                     }
                 }
@@ -76,7 +189,7 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         KeyCode::Home | KeyCode::Char('g') => {
             match app.active_pane {
                 ActivePane::Results => {
-                    app.selected_index = 0;
+                    app.selected_index = app.selectable_row(0, true).unwrap_or(0);
                     app.scroll_offset = 0;
                 }
                 ActivePane::Installed => {
@@ -89,12 +202,12 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
         KeyCode::End | KeyCode::Char('G') => {
             match app.active_pane {
                 ActivePane::Results => {
-                    app.selected_index = app.filtered_packages.len().saturating_sub(1);
+                    app.selected_index = app.filtered_indices.len().saturating_sub(1);
                     let visible_items = app.get_results_visible_items();
                     app.scroll_offset = app.selected_index.saturating_sub(visible_items - 1);
                 }
                 ActivePane::Installed => {
-                    app.installed_selected = app.installed_packages.len().saturating_sub(1);
+                    app.installed_selected = app.installed_view.len().saturating_sub(1);
                     let visible_items = app.get_installed_visible_items();
                     app.installed_scroll = app.installed_selected.saturating_sub(visible_items - 1);
                 }
@@ -102,24 +215,167 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) {
             }
         }
         
+        // Expand a pacman group into its members (Results pane)
+        KeyCode::Char('e') if app.active_pane == ActivePane::Results => {
+            app.expand_selected_group();
+        }
+        
+        // Compare the two selected packages side by side
+        KeyCode::Char('=') => {
+            app.toggle_comparison();
+        }
+        
+        // Copy the install commands for the selection to the clipboard
+        KeyCode::Char('y') if matches!(app.active_pane, ActivePane::Results | ActivePane::Terminal) => {
+            app.copy_install_commands();
+        }
+        
+        // Show or hide debug, doc and lib32 variants
+        KeyCode::Char('H') => {
+            app.toggle_noise_packages();
+        }
+        
+        // Always hide the highlighted package, saved to [search] ignore_patterns
+        KeyCode::Char('X') if app.active_pane == ActivePane::Results => {
+            app.ignore_selected_package();
+        }
+        
+        // Dry-run the selection's install to see what it would pull in
+        KeyCode::Char('p') if matches!(app.active_pane, ActivePane::Results | ActivePane::Terminal) => {
+            app.preview_installation();
+        }
+        
+        // Copy the selection as a runnable shell script
+        KeyCode::Char('Y') if matches!(app.active_pane, ActivePane::Results | ActivePane::Terminal) => {
+            app.export_install_script();
+        }
+        
+        // Pin the highlighted package to a specific version, cycling through known versions
+        KeyCode::Char('v') if app.active_pane == ActivePane::Results => {
+            app.cycle_pinned_version();
+        }
+        
+        // Show which package managers were detected and how they're configured
+        KeyCode::Char('m') => {
+            app.toggle_managers_overview();
+        }
+        
+        // Detect package managers again, e.g. one that was just installed, and reload packages
+        KeyCode::Char('M') => {
+            app.reload_managers = true;
+        }
+        
+        // Show the manager definitions from pkgmanagers/ and any that failed to load
+        KeyCode::Char('P') => {
+            app.toggle_configs_overview();
+        }
+        
+        // Cycle what an empty search lists: everything, installed packages or nothing
+        KeyCode::Char('E') => {
+            app.cycle_empty_query_shows();
+        }
+        
+        // Go back to an older version of the highlighted installed package
+        KeyCode::Char('D') if app.active_pane == ActivePane::Installed => {
+            app.open_downgrade_picker();
+        }
+        
+        // Show only orphaned packages in the Installed pane, or everything again
+        KeyCode::Char('O') if app.active_pane == ActivePane::Installed => {
+            app.toggle_orphans_filter();
+        }
+        
+        // Sort the Installed pane by name, source or size
+        KeyCode::Char('s') if app.active_pane == ActivePane::Installed => {
+            app.cycle_installed_sort();
+        }
+        
+        // Switch between matching names only and names plus descriptions
+        KeyCode::Char('n') => {
+            app.toggle_search_scope();
+        }
+        
+        // Page through the log of commands pmux has executed
+        KeyCode::Char('L') => {
+            app.view_command_log = true;
+        }
+        
+        // Pick one of the highlighted package's optional dependencies, then select it
+        KeyCode::Char('r') if app.active_pane == ActivePane::Results => {
+            app.move_related_cursor();
+        }
+        KeyCode::Char('R') if app.active_pane == ActivePane::Results => {
+            app.toggle_related_selection();
+        }
+        
+        // Edit config.toml and reload it
+        KeyCode::Char(',') => {
+            app.edit_config = true;
+        }
+        
+        // Run the last batch of install/remove commands again
+        KeyCode::Char('.') => {
+            app.rerun_last_batch();
+        }
+        
+        // Remove the packages installed by pmux's last install batch
+        KeyCode::Char('U') => {
+            app.undo_last_install();
+        }
+        
+        // Open the highlighted package's homepage
+        KeyCode::Char('o') => {
+            app.open_selected_url();
+        }
+        
         // Clear selection
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.clear_selection();
         }
         
-        // Install selected packages
-        KeyCode::Enter => {
-            if app.get_selected_count() > 0 {
-                app.start_installation();
-            }
+        // Group Portage results by category; Enter or ←/→ opens and closes a category
+        KeyCode::Char('T') => {
+            app.toggle_category_tree();
+        }
+        KeyCode::Enter | KeyCode::Right if app.active_pane == ActivePane::Results
+            && app.selected_category().is_some() => {
+            let expanded = app.selected_category().is_some_and(|category| app.expanded_categories.contains(category));
+            app.set_category_expanded(key.code == KeyCode::Right || !expanded);
+        }
+        KeyCode::Left if app.active_pane == ActivePane::Results && app.selected_category().is_some() => {
+            app.set_category_expanded(false);
+        }
+        
+        // Section the results by source, under a header per source
+        KeyCode::Char('S') => {
+            app.toggle_group_by_source();
+        }
+        
+        // Install the highlighted result on its own, whatever else is selected
+        KeyCode::Char('I') if app.active_pane == ActivePane::Results => {
+            app.install_highlighted();
         }
         
-        // DO NOT auto-enter search mode on typing - user must explicitly press '/' or 'i'
+        // Install selected packages
+        KeyCode::Enter if app.get_selected_count() > 0 => {
+            app.start_installation();
+        }
         
+        // DO NOT auto-enter search mode on typing - user must explicitly press '/' or 'i'.
+        // Jumping to a name likewise starts with '
         _ => {}
     }
 }
 
+fn typeahead_char(app: &App, key: KeyEvent) -> Option<char> {
+    let KeyCode::Char(c) = key.code else {
+        return None;
+    };
+    let in_list = matches!(app.active_pane, ActivePane::Results | ActivePane::Installed);
+    let plain = (key.modifiers - KeyModifiers::SHIFT).is_empty();
+    (in_list && plain && (c.is_alphanumeric() || "-_.+".contains(c))).then_some(c)
+}
+
 fn handle_editing_mode(app: &mut App, key: KeyEvent) {
     match key.code {
         // Exit editing mode
@@ -130,17 +386,27 @@ fn handle_editing_mode(app: &mut App, key: KeyEvent) {
         
         // Confirm search and move to results
         KeyCode::Enter => {
+            app.apply_pending_search();
+            app.push_search_history();
             app.input_mode = InputMode::Normal;
             app.active_pane = ActivePane::Results;
         }
         
+        // With the cursor at the start of the field (or while recalling), Up/Down walk
+        // through earlier searches instead of jumping to the results
+        KeyCode::Up if (app.cursor_position == 0 || app.history_index.is_some())
+            && app.recall_older_search() => {}
+        KeyCode::Down if app.history_index.is_some() && app.recall_newer_search() => {}
+        
         // Navigation in search - switch to results and navigate
         KeyCode::Up => {
+            app.apply_pending_search();
             app.input_mode = InputMode::Normal;
             app.active_pane = ActivePane::Results;
             app.move_up();
         }
         KeyCode::Down => {
+            app.apply_pending_search();
             app.input_mode = InputMode::Normal;
             app.active_pane = ActivePane::Results;
             app.move_down();
@@ -148,12 +414,18 @@ fn handle_editing_mode(app: &mut App, key: KeyEvent) {
         
         // Multi-selection with Ctrl+Space in search mode
         KeyCode::Char(' ') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            // Toggle selection of current item if we have results
-            if !app.filtered_packages.is_empty() {
-                app.toggle_package_selection();
-            }
+            // Toggle selection of current item; without results nothing is highlighted
+            app.toggle_package_selection();
         }
         
+        // Select all / invert selection within filtered results
+        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.select_all_visible();
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.invert_selection();
+        }
+
         // Regular space adds space to search input
         KeyCode::Char(' ') => {
             app.add_char(' ');
@@ -163,7 +435,19 @@ fn handle_editing_mode(app: &mut App, key: KeyEvent) {
         KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.clear_search();
         }
+
+        // Complete a package manager name at the start of the query, otherwise move on
+        // to the next pane like Tab does elsewhere
+        KeyCode::Tab if app.complete_manager_prefix() => {}
+        KeyCode::Tab => {
+            app.switch_pane();
+        }
         
+        // Paste clipboard contents with Ctrl+V
+        KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.paste_from_clipboard();
+        }
+
         // Text editing
         KeyCode::Char(c) => {
             app.add_char(c);
@@ -176,17 +460,227 @@ fn handle_editing_mode(app: &mut App, key: KeyEvent) {
         }
         
         // Cursor movement
-        KeyCode::Left => {
-            if app.cursor_position > 0 {
-                app.cursor_position -= 1;
-            }
+        KeyCode::Left if app.cursor_position > 0 => {
+            app.cursor_position -= 1;
         }
-        KeyCode::Right => {
-            if app.cursor_position < app.search_input.len() {
-                app.cursor_position += 1;
-            }
+        KeyCode::Right if app.cursor_position < app.search_input.len() => {
+            app.cursor_position += 1;
         }
         
         _ => {}
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::package_managers::Package;
+
+    fn package(name: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            version: Some("1.0-1".to_string()),
+            source: "pacman".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn app_with(names: &[&str]) -> App {
+        let mut app = App::default();
+        app.set_packages(names.iter().map(|name| package(name)).collect());
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        handle_key_event(app, KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn press_ctrl(app: &mut App, c: char) {
+        handle_key_event(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    fn selected_name(app: &App) -> Option<&str> {
+        app.get_selected_package().map(|package| package.name.as_str())
+    }
+
+    #[test]
+    fn esc_leaves_search_before_it_quits() {
+        let mut app = app_with(&["vim"]);
+        press(&mut app, KeyCode::Char('/'));
+        assert_eq!((app.active_pane, app.input_mode), (ActivePane::Search, InputMode::Editing));
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!((app.active_pane, app.input_mode), (ActivePane::Results, InputMode::Normal));
+        assert!(!app.should_quit);
+
+        press(&mut app, KeyCode::Esc);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn esc_closes_popups_without_quitting() {
+        let mut app = app_with(&["vim", "neovim"]);
+        app.compare_packages = Some((package("vim"), package("neovim")));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.compare_packages.is_none());
+        assert!(!app.should_quit);
+
+        app.confirm_commands = vec!["sudo pacman -S vim".to_string()];
+        press(&mut app, KeyCode::Esc);
+        assert!(app.confirm_commands.is_empty());
+        assert!(!app.should_quit);
+    }
+
+    #[test]
+    fn typing_in_search_edits_the_query_not_the_results() {
+        let mut app = app_with(&["vim", "neovim", "emacs"]);
+        press(&mut app, KeyCode::Char('i'));
+        // Bound keys such as q, j and k are text while editing
+        type_text(&mut app, "vqjk");
+        assert_eq!(app.search_input, "vqjk");
+        assert!(!app.should_quit);
+
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(app.search_input, "v ");
+        assert_eq!(app.cursor_position, 2);
+    }
+
+    #[test]
+    fn up_and_down_move_from_search_into_the_results() {
+        let mut app = app_with(&["vim", "neovim", "emacs", "gvim"]);
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "vim");
+
+        // Leaving before the debounce fires still lands in the filtered results
+        press(&mut app, KeyCode::Down);
+        assert_eq!((app.active_pane, app.input_mode), (ActivePane::Results, InputMode::Normal));
+        assert_eq!(app.filtered_indices.len(), 3);
+        assert_eq!(selected_name(&app), Some("neovim"));
+
+        // With no earlier searches to recall, Up from the start of the field goes to the results too
+        press(&mut app, KeyCode::Char('/'));
+        app.cursor_position = 0;
+        press(&mut app, KeyCode::Up);
+        assert_eq!((app.active_pane, app.input_mode), (ActivePane::Results, InputMode::Normal));
+        assert_eq!(selected_name(&app), Some("vim"));
+    }
+
+    #[test]
+    fn ctrl_space_toggles_the_selection_in_both_modes() {
+        let mut app = app_with(&["vim", "neovim"]);
+        press_ctrl(&mut app, ' ');
+        assert!(app.selected_packages.contains("pacman:vim"));
+        press_ctrl(&mut app, ' ');
+        assert!(app.selected_packages.is_empty());
+
+        // While searching it selects the highlighted result instead of typing a space
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "neo");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('/'));
+        press_ctrl(&mut app, ' ');
+        assert_eq!(app.search_input, "neo");
+        assert!(app.selected_packages.contains("pacman:neovim"));
+        assert_eq!(app.active_pane, ActivePane::Search);
+    }
+
+    #[test]
+    fn ctrl_r_inverts_the_selection_in_both_modes() {
+        let mut app = app_with(&["vim", "neovim"]);
+        press_ctrl(&mut app, ' ');
+        press_ctrl(&mut app, 'r');
+        assert_eq!(app.get_selected_packages_list(), vec!["pacman:neovim"]);
+
+        press(&mut app, KeyCode::Char('/'));
+        press_ctrl(&mut app, 'r');
+        assert_eq!(app.get_selected_packages_list(), vec!["pacman:vim"]);
+        assert_eq!(app.search_input, "");
+    }
+
+    #[test]
+    fn install_highlighted_leaves_the_selection_alone() {
+        let mut app = app_with(&["emacs", "vim"]);
+        press_ctrl(&mut app, ' ');
+        press(&mut app, KeyCode::Down);
+
+        // pacman installs need sudo, so they wait for confirmation with only vim in them
+        press(&mut app, KeyCode::Char('I'));
+        assert_eq!(app.confirm_commands.len(), 1);
+        assert!(app.confirm_commands[0].ends_with(" vim"), "{}", app.confirm_commands[0]);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.confirm_commands.is_empty());
+        assert_eq!(app.get_selected_packages_list(), vec!["pacman:emacs".to_string()]);
+
+        press(&mut app, KeyCode::Char('I'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.pending_commands.len(), 1);
+        assert!(app.pending_commands[0].ends_with(" vim"));
+        assert_eq!(app.get_selected_packages_list(), vec!["pacman:emacs".to_string()]);
+    }
+
+    #[test]
+    fn navigation_keys_only_move_in_list_panes() {
+        let mut app = app_with(&["a", "b", "c"]);
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_index, 2);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_index, 2, "stops at the last result without wrap_navigation");
+
+        // Tab goes to the search field, then the details pane, where j/k do nothing
+        press(&mut app, KeyCode::Tab);
+        assert_eq!((app.active_pane, app.input_mode), (ActivePane::Search, InputMode::Editing));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!((app.active_pane, app.input_mode), (ActivePane::Details, InputMode::Normal));
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.selected_index, 2);
+    }
+
+    #[test]
+    fn quote_starts_a_jump_with_bound_letters() {
+        let mut app = app_with(&["vim", "iotop", "curl"]);
+        app.filter_packages();
+        // Letters alone don't jump, bound or not
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(selected_name(&app), Some("vim"));
+
+        press(&mut app, KeyCode::Char('\''));
+        type_text(&mut app, "io");
+        assert_eq!(selected_name(&app), Some("iotop"));
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn navigation_skips_source_headers() {
+        let mut app = app_with(&["vim", "curl", "nano"]);
+        app.packages[1].source = "apt".to_string();
+        app.filter_packages();
+        press(&mut app, KeyCode::Char('S'));
+        assert_eq!(app.source_headers.get(&0), Some(&("pacman".to_string(), 2)));
+        assert_eq!(app.source_headers.get(&3), Some(&("apt".to_string(), 1)));
+        assert_eq!(app.result_count(), 3);
+        assert_eq!(selected_name(&app), Some("vim"));
+        assert_eq!(app.selected_result_position(), 1);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(selected_name(&app), Some("curl"));
+        assert_eq!(app.selected_result_position(), 3);
+
+        press(&mut app, KeyCode::Up);
+        assert_eq!(selected_name(&app), Some("nano"));
+        assert_eq!(app.selected_result_position(), 2);
+
+        press(&mut app, KeyCode::Home);
+        assert_eq!(selected_name(&app), Some("vim"));
+    }
+}
@@ -0,0 +1,59 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+use crate::core::config::LayoutConfig;
+
+// Where each pane goes on a screen of the given size. Navigation uses this too (see
+// App::get_results_visible_items), so scrolling matches what is drawn
+pub struct PaneAreas {
+    pub results: Rect,
+    pub search: Rect,
+    pub details: Rect,
+    pub installed: Rect,
+    pub terminal: Rect,
+    pub status_bar: Rect,
+}
+
+pub fn pane_areas(size: Rect, layout: &LayoutConfig, details_height: u16) -> PaneAreas {
+    // Reserve the bottom row for the status bar
+    let outer_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(size);
+    
+    // Main layout: horizontal split, the right column's width comes from the config
+    let main_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(100 - layout.right_column_width_percent),
+            Constraint::Percentage(layout.right_column_width_percent),
+        ])
+        .split(outer_chunks[0]);
+    
+    // Left side: 5-unit layout (Results -> Search -> Details)
+    let left_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(8),         // Results (top)
+            Constraint::Length(layout.input_field_height), // Search input (center)
+            Constraint::Length(details_height), // Details (bottom)
+        ])
+        .split(main_chunks[0]);
+    
+    // Right side: split between installed and terminal
+    let right_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(layout.installed_list_percent), // Installed packages
+            Constraint::Percentage(layout.terminal_percent),       // Terminal/installation output
+        ])
+        .split(main_chunks[1]);
+    
+    PaneAreas {
+        results: left_chunks[0],
+        search: left_chunks[1],
+        details: left_chunks[2],
+        installed: right_chunks[0],
+        terminal: right_chunks[1],
+        status_bar: outer_chunks[1],
+    }
+}
@@ -0,0 +1,157 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+// Just enough markdown for AUR descriptions and fetched READMEs: `#` headings are bold,
+// `-`/`*`/`+` list items get a bullet, ``` fences and `inline code` are coloured and
+// **bold** is bold. Anything else is shown as written
+pub fn markdown_lines(text: &str) -> Vec<Line<'_>> {
+    let code = Style::default().fg(Color::Yellow);
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(Line::from(Span::styled(line, code)));
+            continue;
+        }
+
+        if let Some(heading) = heading_text(trimmed) {
+            lines.push(Line::from(Span::styled(heading, Style::default().add_modifier(Modifier::BOLD))));
+            continue;
+        }
+
+        let item = ["- ", "* ", "+ "].iter().find_map(|marker| trimmed.strip_prefix(marker));
+        match item {
+            Some(item) => {
+                let indent = line.len() - trimmed.len();
+                let mut spans = vec![Span::raw(format!("{}• ", " ".repeat(indent)))];
+                spans.extend(inline_spans(item));
+                lines.push(Line::from(spans));
+            }
+            None => lines.push(Line::from(inline_spans(line))),
+        }
+    }
+
+    lines
+}
+
+// "## Usage" -> "Usage"; a run of more than six '#' or one without a space isn't a heading
+fn heading_text(line: &str) -> Option<&str> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if rest.is_empty() || rest.starts_with(' ') {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+// Style `code` and **bold** runs. An opening marker without a closing one is left as text
+fn inline_spans(text: &str) -> Vec<Span<'_>> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+
+    loop {
+        let marker = rest
+            .char_indices()
+            .find(|&(i, c)| c == '`' || rest[i..].starts_with("**"))
+            .map(|(i, c)| if c == '`' { (i, "`") } else { (i, "**") });
+        let Some((start, marker)) = marker else {
+            break;
+        };
+
+        let after = &rest[start + marker.len()..];
+        let Some(end) = after.find(marker) else {
+            break;
+        };
+
+        let style = if marker == "`" {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().add_modifier(Modifier::BOLD)
+        };
+        if start > 0 {
+            spans.push(Span::raw(&rest[..start]));
+        }
+        spans.push(Span::styled(&after[..end], style));
+        rest = &after[end + marker.len()..];
+    }
+
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest));
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each line as its spans' (text, style)
+    fn spans(text: &str) -> Vec<Vec<(String, Style)>> {
+        markdown_lines(text)
+            .into_iter()
+            .map(|line| line.spans.into_iter().map(|span| (span.content.into_owned(), span.style)).collect())
+            .collect()
+    }
+
+    fn span(text: &str, style: Style) -> (String, Style) {
+        (text.to_string(), style)
+    }
+
+    #[test]
+    fn headings_are_bold_without_their_hashes() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        assert_eq!(spans("# Title\n### Usage\n#hashtag\n####### seven"), vec![
+            vec![span("Title", bold)],
+            vec![span("Usage", bold)],
+            vec![span("#hashtag", Style::default())],
+            vec![span("####### seven", Style::default())],
+        ]);
+    }
+
+    #[test]
+    fn list_items_get_a_bullet_keeping_their_indent() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        assert_eq!(spans("- one\n  * **two**\n+ three"), vec![
+            vec![span("• ", Style::default()), span("one", Style::default())],
+            vec![span("  • ", Style::default()), span("two", bold)],
+            vec![span("• ", Style::default()), span("three", Style::default())],
+        ]);
+    }
+
+    #[test]
+    fn inline_code_is_coloured_and_unclosed_markers_stay_text() {
+        let code = Style::default().fg(Color::Yellow);
+        assert_eq!(spans("Run `make install` now"), vec![vec![
+            span("Run ", Style::default()),
+            span("make install", code),
+            span(" now", Style::default()),
+        ]]);
+        assert_eq!(spans("a `lone tick"), vec![vec![span("a `lone tick", Style::default())]]);
+    }
+
+    #[test]
+    fn fenced_code_is_coloured_as_written() {
+        let code = Style::default().fg(Color::Yellow);
+        assert_eq!(spans("Build:\n```sh\n# not a heading\n- not a list\n```\ndone"), vec![
+            vec![span("Build:", Style::default())],
+            vec![span("# not a heading", code)],
+            vec![span("- not a list", code)],
+            vec![span("done", Style::default())],
+        ]);
+    }
+}
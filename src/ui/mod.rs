@@ -1,13 +1,19 @@
 mod app;
+mod layout;
 mod render;
 mod events;
+mod result_format;
+mod markdown;
+mod setup;
 
-pub use app::App;
-use render::draw;
-use events::handle_key_event;
+pub use app::{App, ActivePane};
+use app::{format_size, CommandResult};
+use render::{draw, draw_no_managers};
+use events::{handle_key_event, handle_paste_event};
+use setup::run_setup;
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
+    event::{self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, EventStream},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -15,170 +21,652 @@ use ratatui::{
     backend::CrosstermBackend,
     Terminal,
 };
+use futures_util::StreamExt;
 use std::{
     io,
-    thread,
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+    time::MissedTickBehavior,
+};
 
 use crate::core::{
-    local::{detect_package_managers_with_config, LocalPackageManager},
+    cache::Cache,
+    command_log,
+    process,
+    local::{detect_package_managers_with_config, detect_registry_managers, LocalPackageManager},
     config::Config,
-    package_managers::Package,
+    package_managers::{Package, PackageManagerRegistry},
 };
 
-pub async fn run_tui(initial_query: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+// How long quitting waits for background loading to notice the shutdown flag
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+pub async fn run_tui(initial_query: Option<String>, initial_pane: Option<ActivePane>, read_only: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Load configuration and package manager definitions before taking over the terminal
+    let mut app = App::new();
+    app.config.validate()?;
+    let registry = Config::get_config_dir()
+        .and_then(|dir| PackageManagerRegistry::load_from_config_dir(&dir))
+        .unwrap_or_else(|_| PackageManagerRegistry::new());
+    
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
+    // Resize events keep this current; navigation needs it before the first one arrives
+    let size = terminal.size()?;
+    app.terminal_size = (size.width, size.height);
 
-    // Create app
-    let mut app = App::new();
-    if let Some(query) = initial_query {
+    // First launch: ask which of the detected managers to enable instead of writing defaults
+    if !Config::exists() {
+        let mut detected = detect_package_managers_with_config(&app.config);
+        detect_registry_managers(&registry, &mut detected);
+        app.config = run_setup(&mut terminal, &detected)?;
+    }
+
+    // Apply settings from the config
+    let config = app.config.clone();
+    app.search_history = Cache::load_search_history();
+    app.apply_config(config.clone());
+    app.read_only = read_only;
+    if let Some(query) = initial_query.or_else(|| config.main.startup_query.clone()) {
         app.search_input = query;
         app.cursor_position = app.search_input.len();
     }
+    if let Some(pane) = initial_pane {
+        if pane == ActivePane::Search {
+            app.enter_search_mode();
+        } else {
+            app.active_pane = pane;
+        }
+    }
 
-    // Load configuration
-    let config = Config::load().unwrap_or_default();
-    
     // Detect package managers
-    let managers = detect_package_managers_with_config(&config);
+    let mut managers = detect_package_managers_with_config(&config);
+    detect_registry_managers(&registry, &mut managers);
+    if managers.is_empty() {
+        // Explain instead of launching into an empty UI, then exit cleanly
+        terminal.draw(draw_no_managers)?;
+        loop {
+            if let Event::Key(_) = event::read()? {
+                break;
+            }
+        }
+        return restore_terminal(&mut terminal);
+    }
     app.package_managers = managers.clone();
+    app.pm_registry = registry.clone();
+    report_load_errors(&mut app);
 
     // Start background loading
-    let (packages_tx, packages_rx) = std::sync::mpsc::channel();
-    let (installed_tx, installed_rx) = std::sync::mpsc::channel();
-    let (details_tx, details_rx) = std::sync::mpsc::channel();
-    let (aur_tx, aur_rx) = std::sync::mpsc::channel();
+    let (packages_tx, mut packages_rx) = unbounded_channel();
+    let (installed_tx, mut installed_rx) = unbounded_channel();
+    let (details_tx, mut details_rx) = unbounded_channel();
+    let (aur_tx, mut aur_rx) = unbounded_channel();
+    let (loading_messages_tx, mut loading_messages_rx) = unbounded_channel();
+    let (preview_tx, mut preview_rx) = unbounded_channel();
+    let (orphans_tx, mut orphans_rx) = unbounded_channel();
+    let (groups_tx, mut groups_rx) = unbounded_channel();
     
-    start_package_loading(managers, packages_tx, installed_tx);
+    // Only one AUR request is in flight at a time; a newer query aborts the older one
+    let mut aur_task: Option<tokio::task::JoinHandle<()>> = None;
+    let mut aur_query = String::new();
+    
+    // Set on quit so background work stops at the next opportunity
+    let mut shutdown = Arc::new(AtomicBool::new(false));
+    let mut loading_task = start_package_loading(
+        managers,
+        registry,
+        packages_tx.clone(),
+        installed_tx.clone(),
+        loading_messages_tx.clone(),
+        Arc::clone(&shutdown),
+    );
+
+    // Ctrl+C while a command runs interrupts the whole foreground process group. Catching
+    // SIGINT keeps pmux alive, so only the command stops and pmux can report the abort. In
+    // the TUI itself raw mode turns Ctrl+C into a key press, so this changes nothing there
+    #[cfg(unix)]
+    let _interrupts = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())?;
 
-    // Main loop
-    let mut last_tick = Instant::now();
-    let tick_rate = Duration::from_millis(100); // 10 FPS - more reasonable for TUI
+    // Main loop: redraw, then sleep until input, a background result or the next tick
+    let mut events = EventStream::new();
+    let mut ticker = tokio::time::interval(Duration::from_millis(100)); // 10 FPS - keeps spinners and debounce moving
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
     
     loop {
-        // Handle incoming packages
-        if let Ok(packages) = packages_rx.try_recv() {
-            app.set_packages(packages);
-        }
-        if let Ok(installed) = installed_rx.try_recv() {
-            app.set_installed_packages(installed);
-        }
-        
-        // Handle incoming package details
-        if let Ok((package, details)) = details_rx.try_recv() {
-            app.set_package_details(&package, details);
-        }
-        
-        // Handle incoming AUR packages
-        if let Ok(aur_packages) = aur_rx.try_recv() {
-            app.add_aur_packages(aur_packages);
-        }
-        
         // Update search if debounce time has passed
         app.update_search_if_needed();
         
-        // Trigger AUR search if search input has changed and contains text
-        if !app.search_input.is_empty() && app.should_update_search() {
-            let query = app.search_input.clone();
-            if query.len() >= 2 { // Only search if query is at least 2 characters
+        // Trigger AUR search once the debounced search input settles on a new query
+        if app.should_update_search() && app.search_input != aur_query {
+            aur_query = app.search_input.clone();
+            if let Some(task) = aur_task.take() {
+                task.abort();
+            }
+            // AUR results carry no repo, so there's nothing to fetch while filtering by one
+            // or by another manager, or on systems without pacman or paru
+            let search = app.parse_search(&aur_query);
+            let wants_aur = app.manager_names().iter().any(|name| name == "aur")
+                && search.repo.is_none()
+                && search.source.as_deref().is_none_or(|source| source == "aur");
+            if wants_aur && search.text.len() >= 2 { // Only search if query is at least 2 characters
+                let query = aur_query.clone();
                 let aur_tx_clone = aur_tx.clone();
-                tokio::spawn(async move {
-                    if let Ok(aur_packages) = search_aur_async(&query).await {
-                        let _ = aur_tx_clone.send(aur_packages);
-                    }
-                });
+                app.aur_search_started = Some(Instant::now());
+                aur_task = Some(tokio::spawn(async move {
+                    let result = search_aur_async(&search.text).await.map_err(|e| e.to_string());
+                    let _ = aur_tx_clone.send((query, result));
+                }));
+            } else {
+                app.aur_search_started = None;
             }
         }
         
         // Fetch package details if needed
         if app.should_fetch_details() {
             if let Some(package) = app.get_selected_package().cloned() {
-                if app.get_package_details(&package).is_none() {
+                if app.get_package_details(&package).is_none() && app.mark_details_requested(&package) {
+                    fetch_package_details_async(package, details_tx.clone());
+                }
+            }
+            
+            // And its neighbours, so moving onto them shows details straight away
+            for package in app.details_to_prefetch() {
+                if app.mark_details_requested(&package) {
                     fetch_package_details_async(package, details_tx.clone());
                 }
             }
         }
+        
+        // Fetch details for both sides of a comparison
+        if let Some((left, right)) = app.compare_packages.clone() {
+            for package in [left, right] {
+                if app.get_package_details(&package).is_none() && app.mark_details_requested(&package) {
+                    fetch_package_details_async(package, details_tx.clone());
+                }
+            }
+        }
+
+        // Pick up managers installed or given a definition since startup. The previous load
+        // is told to stop so its results can't replace the new ones
+        if std::mem::take(&mut app.reload_managers) {
+            let registry = Config::get_config_dir()
+                .and_then(|dir| PackageManagerRegistry::load_from_config_dir(&dir))
+                .unwrap_or_else(|_| PackageManagerRegistry::new());
+            let mut managers = detect_package_managers_with_config(&app.config);
+            detect_registry_managers(&registry, &mut managers);
+            
+            if managers.is_empty() {
+                app.set_status("No package managers found, keeping the current ones".to_string());
+                app.pm_registry.load_errors = registry.load_errors;
+            } else {
+                let added: Vec<&str> = managers
+                    .iter()
+                    .map(|manager| manager.name.as_str())
+                    .filter(|name| !app.package_managers.iter().any(|known| known.name == *name))
+                    .collect();
+                app.set_status(if added.is_empty() {
+                    "Reloading packages".to_string()
+                } else {
+                    format!("Found {}, reloading packages", added.join(", "))
+                });
+                
+                shutdown.store(true, Ordering::Relaxed);
+                shutdown = Arc::new(AtomicBool::new(false));
+                app.package_managers = managers.clone();
+                app.pm_registry = registry.clone();
+                loading_task = start_package_loading(
+                    managers,
+                    registry,
+                    packages_tx.clone(),
+                    installed_tx.clone(),
+                    loading_messages_tx.clone(),
+                    Arc::clone(&shutdown),
+                );
+            }
+            report_load_errors(&mut app);
+        }
 
+        // Dry runs don't need the terminal, so they run in the background
+        for command in std::mem::take(&mut app.pending_previews) {
+            run_preview_async(command, preview_tx.clone());
+        }
+        for (source, command) in std::mem::take(&mut app.pending_orphan_queries) {
+            run_orphans_async(source, command, orphans_tx.clone());
+        }
+        for group in std::mem::take(&mut app.pending_group_expansions) {
+            run_group_expansion_async(group, groups_tx.clone());
+        }
+
+        // Run anything the key handlers queued, outside the TUI so sudo prompts work
+        if !app.pending_commands.is_empty() || app.view_command_log || app.edit_config {
+            // Stop crossterm's reader thread so it can't swallow input meant for the command
+            drop(events);
+            
+            let mut results = Vec::new();
+            let mut commands = std::mem::take(&mut app.pending_commands).into_iter();
+            for command in commands.by_ref() {
+                let status = execute_command(&mut terminal, &command)?;
+                if let Err(e) = command_log::record(&command, status) {
+                    log::warn!("Failed to write command log: {}", e);
+                }
+                
+                let result = CommandResult { command, status };
+                app.push_terminal_output(format!("$ {}", result.command));
+                app.push_terminal_output(match status {
+                    Some(status) if status.success() => "Done".to_string(),
+                    Some(_) if result.interrupted() => "Aborted with Ctrl+C".to_string(),
+                    Some(status) => format!("Failed ({})", status),
+                    None => "Failed to start".to_string(),
+                });
+                let interrupted = result.interrupted();
+                results.push(result);
+                
+                // Ctrl+C is for stopping the whole batch, e.g. a wrong selection
+                if interrupted {
+                    break;
+                }
+            }
+            for command in commands {
+                app.push_terminal_output(format!("Skipped after the abort: {}", command));
+            }
+            app.record_last_install(&results);
+            app.install_summary = results;
+            // The installs may have upgraded some of them; the next load counts again
+            if !app.install_summary.is_empty() {
+                app.updates_available = None;
+            }
+            
+            if std::mem::take(&mut app.view_command_log) {
+                view_command_log(&mut terminal, &mut app)?;
+            }
+            if std::mem::take(&mut app.edit_config) {
+                edit_config(&mut terminal, &mut app)?;
+            }
+            
+            events = EventStream::new();
+        }
+        
         // Draw UI
         terminal.draw(|f| draw(f, &app))?;
 
-        // Handle events
-        let timeout = tick_rate.saturating_sub(last_tick.elapsed());
-        if event::poll(timeout)? {
-            match event::read()? {
-                Event::Key(key) => {
+        tokio::select! {
+            Some(packages) = packages_rx.recv() => {
+                app.set_packages(packages);
+            }
+            Some(installed) = installed_rx.recv() => {
+                app.set_installed_packages(installed);
+            }
+            // Managers that failed or timed out while loading
+            Some(message) = loading_messages_rx.recv() => {
+                app.push_terminal_output(message);
+            }
+            Some((package, details)) = details_rx.recv() => {
+                app.set_package_details(&package, details);
+            }
+            Some((command, output)) = preview_rx.recv() => {
+                app.push_preview_output(&command, output);
+            }
+            Some((source, output)) = orphans_rx.recv() => {
+                app.add_orphans(&source, output);
+            }
+            Some((group, output)) = groups_rx.recv() => {
+                app.add_group_members(&group, output);
+            }
+            // Drop AUR results for a query the user has since changed
+            Some((query, result)) = aur_rx.recv() => {
+                if query == app.search_input {
+                    app.aur_search_started = None;
+                    match result {
+                        Ok(aur_packages) => {
+                            app.set_status(format!("AUR: {} results", aur_packages.len()));
+                            app.add_aur_packages(aur_packages);
+                        }
+                        Err(e) => app.set_status(format!("AUR: {}", e)),
+                    }
+                }
+            }
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) => {
                     handle_key_event(&mut app, key);
                 }
-                Event::Resize(width, height) => {
+                Some(Ok(Event::Paste(text))) => {
+                    handle_paste_event(&mut app, &text);
+                }
+                Some(Ok(Event::Resize(width, height))) => {
                     app.terminal_size = (width, height);
                 }
-                _ => {}
-            }
-        }
-
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
+            _ = ticker.tick() => {}
         }
 
         if app.should_quit {
             break;
         }
     }
+    
+    // Stop background work before handing the terminal back to the shell
+    shutdown.store(true, Ordering::Relaxed);
+    if let Some(task) = aur_task.take() {
+        task.abort();
+    }
+    // A listing command can take a while to return; don't hold up quitting for it
+    let _ = tokio::time::timeout(SHUTDOWN_GRACE, loading_task).await;
 
-    // Restore terminal
+    restore_terminal(&mut terminal)
+}
+
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<(), Box<dyn std::error::Error>> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
     Ok(())
 }
 
+fn resume_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<(), Box<dyn std::error::Error>> {
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+
+    Ok(())
+}
+
+// Hand the terminal over to a shell command, then take it back once the user has read the output
+fn execute_command(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    command: &str,
+) -> Result<Option<ExitStatus>, Box<dyn std::error::Error>> {
+    restore_terminal(terminal)?;
+
+    println!("$ {}", command);
+    println!("(Ctrl+C aborts this and the rest of the batch)");
+    let status = std::process::Command::new("sh")
+        .args(["-c", command])
+        .status()
+        .ok();
+
+    println!();
+    println!("Press Enter to return to pmux");
+    let _ = io::stdin().read_line(&mut String::new());
+
+    resume_terminal(terminal)?;
+    Ok(status)
+}
+
+fn view_command_log(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = match command_log::get_log_file() {
+        Ok(path) if path.exists() => path,
+        _ => {
+            app.push_terminal_output("No commands have been logged yet".to_string());
+            return Ok(());
+        }
+    };
+
+    // Open at the end of the file, like tail
+    restore_terminal(terminal)?;
+    let _ = std::process::Command::new("sh")
+        .args(["-c", "${PAGER:-less +G} \"$1\"", "sh"])
+        .arg(&path)
+        .status();
+    resume_terminal(terminal)
+}
+
+// Broken definitions would otherwise just be missing from the detected managers
+fn report_load_errors(app: &mut App) {
+    let failed = app.pm_registry.load_errors.len();
+    if failed > 0 {
+        app.set_status(format!("{} manager definition(s) failed to load, P shows why", failed));
+    }
+}
+
+// Open config.toml in $EDITOR, then apply it without restarting. A config that no longer
+// parses or validates is reported and the one already running is kept
+fn edit_config(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Config::get_config_path()?;
+    if !path.exists() {
+        if let Err(e) = Config::default().save() {
+            app.push_terminal_output(format!("Failed to create {}: {}", path.display(), e));
+            return Ok(());
+        }
+    }
+
+    restore_terminal(terminal)?;
+    let _ = std::process::Command::new("sh")
+        .args(["-c", "${VISUAL:-${EDITOR:-vi}} \"$1\"", "sh"])
+        .arg(&path)
+        .status();
+    resume_terminal(terminal)?;
+
+    match Config::load().and_then(|config| config.validate().map(|()| config)) {
+        Ok(config) => {
+            // Detection depends on [pm] and [bedrock_linux], and definitions may have been
+            // edited alongside; anything else applies without reloading packages
+            let registry = Config::get_config_dir()
+                .and_then(|dir| PackageManagerRegistry::load_from_config_dir(&dir))
+                .unwrap_or_else(|_| PackageManagerRegistry::new());
+            app.reload_managers = config.pm != app.config.pm
+                || config.bedrock_linux != app.config.bedrock_linux
+                || registry.managers != app.pm_registry.managers
+                || registry.load_errors != app.pm_registry.load_errors;
+            app.apply_config(config);
+            app.set_status("Config reloaded".to_string());
+        }
+        Err(e) => {
+            app.push_terminal_output(format!("Config not reloaded: {}", e));
+            app.set_status("Config has errors, keeping the previous one".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Listing runs external commands and reads package databases, so it goes on the blocking pool
 fn start_package_loading(
     managers: Vec<LocalPackageManager>,
-    packages_tx: std::sync::mpsc::Sender<Vec<Package>>,
-    installed_tx: std::sync::mpsc::Sender<Vec<Package>>,
-) {
-    thread::spawn(move || {
+    registry: PackageManagerRegistry,
+    packages_tx: UnboundedSender<Vec<Package>>,
+    installed_tx: UnboundedSender<Vec<Package>>,
+    messages_tx: UnboundedSender<String>,
+    shutdown: Arc<AtomicBool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::task::spawn_blocking(move || {
         let mut all_packages = Vec::new();
         let mut all_installed = Vec::new();
 
         for manager in &managers {
+            if shutdown.load(Ordering::Relaxed) {
+                return;
+            }
+            
+            // Managers without built-in support run their configured commands
+            let custom_config = if manager.is_builtin() {
+                None
+            } else {
+                registry.get_manager(&manager.name)
+            };
+            
+            // When reading a built-in manager's database finds nothing, try its configured
+            // command instead. Bedrock strata are skipped since the command would run on the host
+            let fallback_config = if manager.is_builtin() && manager.stratum.is_none() {
+                registry.get_manager(&manager.name)
+            } else {
+                None
+            };
+            
             // Load installed packages
-            if let Ok(mut installed) = manager.list_installed() {
-                for pkg in &mut installed {
-                    pkg.installed = true;
+            let installed = match custom_config {
+                Some(config) => registry.list_installed(config),
+                None => manager.list_installed().and_then(|installed| match fallback_config {
+                    Some(config) if installed.is_empty() => registry.list_installed(config),
+                    _ => Ok(installed),
+                }),
+            };
+            match installed {
+                Ok(mut installed) => {
+                    for pkg in &mut installed {
+                        pkg.installed = true;
+                    }
+                    all_installed.extend(installed.clone());
+                    all_packages.extend(installed);
+                }
+                Err(e) => {
+                    let _ = messages_tx.send(format!("{}: listing installed packages failed: {}", manager.name, e));
                 }
-                all_installed.extend(installed.clone());
-                all_packages.extend(installed);
             }
 
             // Load available packages
-            if let Ok(available) = manager.list_available() {
-                all_packages.extend(available);
+            let available = match custom_config {
+                Some(config) => registry.list_packages(config),
+                None => manager.list_available().and_then(|available| match fallback_config {
+                    Some(config) if available.is_empty() => registry.list_packages(config),
+                    _ => Ok(available),
+                }),
+            };
+            match available {
+                Ok(available) => all_packages.extend(available),
+                Err(e) => {
+                    let _ = messages_tx.send(format!("{}: listing available packages failed: {}", manager.name, e));
+                }
             }
         }
 
+        if shutdown.load(Ordering::Relaxed) {
+            return;
+        }
+
         // Send results
         let _ = installed_tx.send(all_installed);
         let _ = packages_tx.send(all_packages);
+    })
+}
+
+// `pacman -Si`-style details from what list_pacman_available read out of the sync database
+fn cached_pacman_details(package: &Package) -> Option<String> {
+    if package.source != "pacman" || package.is_group {
+        return None;
+    }
+    let repo = package.repo.as_ref()?;
+    let description = package.description.as_ref()?;
+    
+    let list = |values: &[String]| if values.is_empty() { "None".to_string() } else { values.join("  ") };
+    let mut details = String::new();
+    details.push_str(&format!("Repository      : {}\n", repo));
+    details.push_str(&format!("Name            : {}\n", package.name));
+    details.push_str(&format!("Version         : {}\n", package.version.as_deref().unwrap_or("unknown")));
+    details.push_str(&format!("Description     : {}\n", description));
+    details.push_str(&format!("URL             : {}\n", package.url.as_deref().unwrap_or("None")));
+    details.push_str(&format!("Provides        : {}\n", list(&package.provides)));
+    details.push_str(&format!("Conflicts With  : {}\n", list(&package.conflicts)));
+    if let Some(size) = package.download_size {
+        details.push_str(&format!("Download Size   : {}\n", format_size(size)));
+    }
+    if let Some(size) = package.installed_size {
+        details.push_str(&format!("Installed Size  : {}\n", format_size(size)));
+    }
+    Some(details)
+}
+
+// Dry runs such as `dnf install --assumeno` exit non-zero by design, so the output is
+// shown whatever the exit status
+fn run_preview_async(command: String, preview_tx: UnboundedSender<(String, Result<String, String>)>) {
+    tokio::task::spawn_blocking(move || {
+        let output = process::output_with_timeout(std::process::Command::new("sh").args(["-c", &command]))
+            .map(|output| {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                text
+            })
+            .map_err(|e| e.to_string());
+        let _ = preview_tx.send((command, output));
+    });
+}
+
+// `pacman -Qdtq` exits 1 when there's nothing to report, so only a failure that says
+// something counts as one
+fn run_orphans_async(source: String, command: String, orphans_tx: UnboundedSender<(String, Result<String, String>)>) {
+    tokio::task::spawn_blocking(move || {
+        let output = process::output_with_timeout(std::process::Command::new("sh").args(["-c", &command]))
+            .map_err(|e| e.to_string())
+            .and_then(|output| {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                if output.status.success() || stderr.is_empty() {
+                    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+                } else {
+                    Err(stderr)
+                }
+            });
+        let _ = orphans_tx.send((source, output));
+    });
+}
+
+fn run_group_expansion_async(group: String, groups_tx: UnboundedSender<(String, Result<String, String>)>) {
+    tokio::task::spawn_blocking(move || {
+        let output = process::output_with_timeout(std::process::Command::new("pacman").args(["-Sg", &group]))
+            .map_err(|e| e.to_string())
+            .and_then(|output| {
+                if output.status.success() {
+                    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+                } else {
+                    Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+                }
+            });
+        let _ = groups_tx.send((group, output));
     });
 }
 
 fn fetch_package_details_async(
     package: Package,
-    details_tx: std::sync::mpsc::Sender<(Package, String)>,
+    details_tx: UnboundedSender<(Package, String)>,
 ) {
-    thread::spawn(move || {
+    // AUR packages are described by the RPC API, so this works without the paru binary
+    if package.source == "aur" || package.source == "paru" {
+        tokio::spawn(async move {
+            use crate::core::aur::AurClient;
+            let details = match AurClient::new().get_package_details(&package.name).await {
+                Ok(details) => details,
+                Err(_) => format!("Failed to fetch AUR details for {}", package.name),
+            };
+            let _ = details_tx.send((package, details));
+        });
+        return;
+    }
+    
+    // Sync database entries were already parsed while loading, so there's no need to run pacman
+    if let Some(details) = cached_pacman_details(&package) {
+        let _ = details_tx.send((package, details));
+        return;
+    }
+    
+    tokio::task::spawn_blocking(move || {
         let details = match package.source.as_str() {
             "pacman" => {
                 std::process::Command::new("pacman")
@@ -189,15 +677,6 @@ fn fetch_package_details_async(
                     .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
                     .unwrap_or_else(|| format!("No details available for {}", package.name))
             }
-            "paru" => {
-                std::process::Command::new("paru")
-                    .args(&["-Si", &package.name])
-                    .output()
-                    .ok()
-                    .filter(|output| output.status.success())
-                    .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
-                    .unwrap_or_else(|| format!("No AUR details available for {}", package.name))
-            }
             "dnf" => {
                 std::process::Command::new("dnf")
                     .args(&["info", &package.name])
@@ -234,6 +713,46 @@ fn fetch_package_details_async(
                     .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
                     .unwrap_or_else(|| format!("No APT details available for {}", package.name))
             }
+            "pkg" => {
+                // `pkg info` only knows installed packages; fall back to the remote catalog
+                std::process::Command::new("pkg")
+                    .args(["info", &package.name])
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .or_else(|| {
+                        std::process::Command::new("pkg")
+                            .args(["search", "-f", &package.name])
+                            .output()
+                            .ok()
+                            .filter(|output| output.status.success())
+                    })
+                    .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+                    .unwrap_or_else(|| format!("No pkg details available for {}", package.name))
+            }
+            "opkg" => {
+                process::output_with_timeout(std::process::Command::new("opkg").args(["info", &package.name]))
+                    .ok()
+                    .filter(|output| output.status.success() && !output.stdout.is_empty())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+                    .unwrap_or_else(|| format!("No opkg details available for {}", package.name))
+            }
+            "pkgin" => {
+                process::output_with_timeout(std::process::Command::new("pkgin").args(["pkg-descr", &package.name]))
+                    .ok()
+                    .filter(|output| output.status.success() && !output.stdout.is_empty())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+                    .unwrap_or_else(|| format!("No pkgin details available for {}", package.name))
+            }
+            "winget" => {
+                process::output_with_timeout(
+                    std::process::Command::new("winget.exe").args(["show", "--exact", "--id", &package.name])
+                )
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+                    .unwrap_or_else(|| format!("No winget details available for {}", package.name))
+            }
             _ => format!("Package: {}\nSource: {}", package.name, package.source),
         };
 
@@ -2,46 +2,101 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
+use std::io::IsTerminal;
 
-use crate::ui::app::{App, ActivePane, InputMode};
+use crate::core::config::Config;
+use crate::core::local::DETECTION_HINTS;
+use crate::core::package_managers::{InstallReason, Package};
+use crate::ui::app::{format_size, App, ActivePane, DowngradePicker, InputMode, ManagerStatus};
+use crate::ui::layout::pane_areas;
+use crate::ui::markdown::markdown_lines;
 
 pub fn draw(f: &mut Frame, app: &App) {
-    let size = f.size();
-    
-    // Main layout: horizontal split (70% left, 30% right)
-    let main_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .split(size);
-    
-    // Left side: 5-unit layout (Results -> Search -> Details)
-    let left_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Min(8),         // Results (top)
-            Constraint::Length(3),      // Search input (center)
-            Constraint::Length(8),      // Details (bottom)
-        ])
-        .split(main_chunks[0]);
-    
-    // Right side: split between installed and terminal
-    let right_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(60), // Installed packages
-            Constraint::Percentage(40), // Terminal/installation output
-        ])
-        .split(main_chunks[1]);
+    let areas = pane_areas(f.size(), &app.config.layout, app.details_height());
     
     // Draw components in new order
-    draw_results(f, app, left_chunks[0]);      // Top left
-    draw_search_input(f, app, left_chunks[1]); // Center left
-    draw_details(f, app, left_chunks[2]);      // Bottom left
-    draw_installed(f, app, right_chunks[0]);   // Top right
-    draw_terminal(f, app, right_chunks[1]);    // Bottom right
+    draw_results(f, app, areas.results);       // Top left
+    draw_search_input(f, app, areas.search);   // Center left
+    draw_details(f, app, areas.details);       // Bottom left
+    draw_installed(f, app, areas.installed);   // Top right
+    draw_terminal(f, app, areas.terminal);     // Bottom right
+    draw_status_bar(f, app, areas.status_bar); // Bottom row
+    
+    if !app.confirm_commands.is_empty() {
+        draw_install_confirmation(f, app);
+    } else if !app.install_summary.is_empty() {
+        draw_install_summary(f, app);
+    } else if let Some(managers) = &app.managers_overview {
+        draw_managers_overview(f, app, managers);
+    } else if app.configs_overview {
+        draw_configs_overview(f, app);
+    } else if let Some(picker) = &app.downgrade_picker {
+        draw_downgrade_picker(f, picker);
+    }
+}
+
+fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
+    let result_count = app.last_filter_time.map(|elapsed| {
+        let (results, millis) = (app.result_count(), elapsed.as_millis());
+        app.result_count_text.get((results, millis), || {
+            let took = if millis == 0 { "<1ms".to_string() } else { format!("{}ms", millis) };
+            format!(" {} results in {}", results, took)
+        })
+    });
+    let line = match (app.current_status(), app.aur_search_started) {
+        (Some(message), _) => Line::from(Span::styled(message, Style::default().fg(Color::Yellow))),
+        (None, Some(started)) => {
+            let frame = (started.elapsed().as_millis() / 100) as usize % SPINNER.len();
+            Line::from(Span::styled(format!(" {} searching AUR…", SPINNER[frame]), Style::default().fg(Color::Cyan)))
+        }
+        (None, None) => {
+            let mut spans = Vec::new();
+            if app.read_only {
+                spans.push(Span::styled(" READ-ONLY ", Style::default().fg(Color::Black).bg(Color::Yellow)));
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::styled(
+                format!(" [{}]", if app.name_only_search { "name" } else { "name+desc" }),
+                Style::default().fg(Color::Cyan)
+            ));
+            if !app.hide_noise {
+                spans.push(Span::styled(" [+dbg/doc/lib32]", Style::default().fg(Color::Cyan)));
+            }
+            if app.category_tree {
+                spans.push(Span::styled(" [tree]", Style::default().fg(Color::Cyan)));
+            }
+            if app.group_by_source {
+                spans.push(Span::styled(" [by source]", Style::default().fg(Color::Cyan)));
+            }
+            match app.updates_available {
+                Some(0) => spans.push(Span::styled(" up to date", Style::default().fg(Color::Green))),
+                Some(1) => spans.push(Span::styled(" 1 update available", Style::default().fg(Color::Yellow))),
+                Some(count) => spans.push(Span::styled(
+                    format!(" {} updates available", count),
+                    Style::default().fg(Color::Yellow)
+                )),
+                None => {}
+            }
+            if let Some(result_count) = &result_count {
+                spans.push(Span::styled(
+                    &**result_count,
+                    Style::default().fg(Color::Gray)
+                ));
+            }
+            let hints = if app.read_only {
+                "  /: search  ': jump  n: scope  Ctrl+Space: select  y: copy command  Tab: switch pane  q: quit"
+            } else {
+                "  /: search  ': jump  n: scope  Ctrl+Space: select  Enter: install  y: copy command  Tab: switch pane  q: quit"
+            };
+            spans.push(Span::styled(hints, Style::default().fg(Color::DarkGray)));
+            Line::from(spans)
+        }
+    };
+    
+    f.render_widget(Paragraph::new(line), area);
 }
 
 fn draw_search_input(f: &mut Frame, app: &App, area: Rect) {
@@ -56,8 +111,8 @@ fn draw_search_input(f: &mut Frame, app: &App, area: Rect) {
         .borders(Borders::ALL)
         .border_style(input_style);
     
-    let selection_info = if !app.filtered_packages.is_empty() {
-        format!("({}/{}) ", app.selected_index + 1, app.filtered_packages.len())
+    let selection_info = if !app.filtered_indices.is_empty() {
+        format!("({}/{}) ", app.selected_result_position(), app.result_count())
     } else {
         String::new()
     };
@@ -94,81 +149,138 @@ fn draw_search_input(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn draw_results(f: &mut Frame, app: &App, area: Rect) {
-    let border_style = if app.active_pane == ActivePane::Results {
-        Style::default().fg(Color::Green)
-    } else {
-        Style::default().fg(Color::Gray)
-    };
+    let border_style = unit_border_style(app, "results_unit", app.active_pane == ActivePane::Results);
     
     // Calculate visible range
     let visible_height = area.height.saturating_sub(2) as usize;
     let start = app.scroll_offset;
-    let end = (start + visible_height).min(app.filtered_packages.len());
-    
-    let scroll_info = if app.filtered_packages.len() > visible_height {
-        format!(" ({}-{}/{}) ", 
-            start + 1, 
-            end, 
-            app.filtered_packages.len()
-        )
-    } else {
-        format!(" ({}) ", app.filtered_packages.len())
-    };
-    let title = format!(" Results{} ", scroll_info);
+    let end = (start + visible_height).min(app.filtered_indices.len());
+    
+    let rows = app.filtered_indices.len();
+    let title = app.results_title.get((start, end, rows, visible_height), || {
+        if rows > visible_height {
+            format!(" Results ({}-{}/{})  ", start + 1, end, rows)
+        } else {
+            format!(" Results ({})  ", rows)
+        }
+    });
     
     let block = Block::default()
-        .title(title)
+        .title(&*title)
         .borders(Borders::ALL)
         .border_style(border_style);
     
-    let items: Vec<ListItem> = app.filtered_packages[start..end]
+    // The cursor symbol takes up the start of every row
+    let cursor_symbol = app.config.ui.cursor_symbol.as_str();
+    let row_width = (area.width.saturating_sub(2) as usize).saturating_sub(cursor_symbol.chars().count());
+    let installed_colour = text_colour(app, "results_unit_installed_text", Color::Green);
+    let highlight_colour = text_colour(app, "results_unit_highlight_text", Color::White);
+    let text = text_colour(app, "results_unit_text", Color::White);
+    let items: Vec<ListItem> = app.filtered_indices[start..end]
         .iter()
+        .map(|&index| &app.packages[index])
         .enumerate()
         .map(|(i, package)| {
             let actual_index = start + i;
             let is_selected = actual_index == app.selected_index;
             
-            // Format: "[x] name                    ✓ source"
-            let selected_indicator = if app.is_package_selected(package) { "●" } else { " " };
-            let installed_indicator = if package.installed { "✓" } else { " " };
-            let content = format!("{} {:<38} {} {}", 
-                selected_indicator,
-                package.name, 
-                installed_indicator, 
-                package.source
-            );
+            if let Some((source, count)) = app.source_headers.get(&actual_index) {
+                let header = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+                return ListItem::new(format!("── {} ({}) ──", source, count)).style(header);
+            }
+            if let Some((category, count)) = app.category_headers.get(&actual_index) {
+                let marker = if app.expanded_categories.contains(category) { "▾" } else { "▸" };
+                let style = if is_selected {
+                    Style::default().bg(Color::DarkGray).fg(Color::White)
+                } else {
+                    Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)
+                };
+                return ListItem::new(format!("{} {}/ ({})", marker, category, count)).style(style);
+            }
+            
+            // Default format: "● name                    ✓ source       version" ([layout] result_format)
+            let mut content = app.result_format.render(package, app.is_package_selected(package));
+            let provide = app.matched_provide(package);
+            if let Some(provide) = provide {
+                content.push_str(&format!(" (provides {})", provide));
+            }
+            // Packages under a category header are indented
+            if app.category_tree && package.source == "emerge" && package.name.contains('/') {
+                content.insert_str(0, "  ");
+            }
+            if !app.result_format.has_version() {
+                content = with_version_column(content, package.version.as_deref(), row_width);
+            }
             
             let style = if is_selected {
-                Style::default().bg(Color::DarkGray).fg(Color::White)
+                Style::default().bg(Color::DarkGray).fg(highlight_colour)
+            } else if package.is_group {
+                Style::default().fg(Color::Magenta)
+            } else if provide.is_some() {
+                Style::default().fg(Color::Cyan)
             } else if package.installed {
-                Style::default().fg(Color::Green)
+                Style::default().fg(installed_colour)
             } else {
-                Style::default().fg(Color::White)
+                Style::default().fg(text)
             };
             
             ListItem::new(content).style(style)
         })
         .collect();
     
-    let list = List::new(items).block(block);
+    let list = List::new(items).block(block).highlight_symbol(cursor_symbol);
     
-    let mut list_state = ListState::default();
-    if !app.filtered_packages.is_empty() && app.selected_index < app.filtered_packages.len() {
-        // Convert absolute index to relative index for display
-        if app.selected_index >= start && app.selected_index < end {
-            list_state.select(Some(app.selected_index - start));
-        }
-    }
+    // Convert absolute index to relative index for display; the rows are already the visible slice
+    let mut list_state = app.results_list_state.borrow_mut();
+    *list_state.offset_mut() = 0;
+    list_state.select((start..end).contains(&app.selected_index).then(|| app.selected_index - start));
     
-    f.render_stateful_widget(list, area, &mut list_state);
+    f.render_stateful_widget(list, area, &mut *list_state);
 }
 
-fn draw_details(f: &mut Frame, app: &App, area: Rect) {
-    let border_style = if app.active_pane == ActivePane::Details {
-        Style::default().fg(Color::Green)
+// A colour from [text_colours], written as "#rrggbb" or a name such as "lightgreen".
+// Missing or unreadable ones fall back to the built-in colour
+fn text_colour(app: &App, key: &str, fallback: Color) -> Color {
+    app.config.text_colours.get(key).and_then(|colour| colour.parse().ok()).unwrap_or(fallback)
+}
+
+// A pane's border: its [border_colours] entry, or its highlight text colour while focused
+fn unit_border_style(app: &App, unit: &str, focused: bool) -> Style {
+    let colour = if focused {
+        text_colour(app, &format!("{}_highlight_text", unit), Color::Green)
     } else {
-        Style::default().fg(Color::Gray)
+        app.config.border_colours.get(unit).and_then(|colour| colour.parse().ok()).unwrap_or(Color::Gray)
+    };
+    Style::default().fg(colour)
+}
+
+// Right-align the version in whatever space the row leaves, truncating long ones
+fn with_version_column(row: String, version: Option<&str>, width: usize) -> String {
+    let used = row.chars().count();
+    let Some(version) = version else {
+        return row;
+    };
+    // Keep at least a two-space gap between the row and the version
+    let available = width.saturating_sub(used + 2);
+    if available < 4 {
+        return row;
+    }
+    
+    let version: String = if version.chars().count() > available {
+        version.chars().take(available - 1).chain(std::iter::once('…')).collect()
+    } else {
+        version.to_string()
     };
+    format!("{}{:>pad$}", row, version, pad = width - used)
+}
+
+fn draw_details(f: &mut Frame, app: &App, area: Rect) {
+    let border_style = unit_border_style(app, "description_unit", app.active_pane == ActivePane::Details);
+    
+    if let Some((left, right)) = &app.compare_packages {
+        draw_comparison(f, app, area, left, right, border_style);
+        return;
+    }
     
     let block = Block::default()
         .title(" Package Details ")
@@ -176,85 +288,223 @@ fn draw_details(f: &mut Frame, app: &App, area: Rect) {
         .border_style(border_style);
     
     let content = if let Some(package) = app.get_selected_package() {
-        if let Some(details) = app.get_package_details(package) {
-            // Show cached details
-            details.lines().map(Line::from).collect()
-        } else {
-            // Show basic info while loading
-            let mut lines = vec![
-                Line::from(vec![
-                    Span::styled("Package: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(&package.name),
-                ]),
-                Line::from(vec![
-                    Span::styled("Source: ", Style::default().fg(Color::Cyan)),
-                    Span::raw(&package.source),
-                ]),
-            ];
-            
-            if let Some(version) = &package.version {
-                lines.push(Line::from(vec![
-                    Span::styled("Version: ", Style::default().fg(Color::Magenta)),
-                    Span::raw(version),
-                ]));
-            }
-            
-            if let Some(description) = &package.description {
-                lines.push(Line::from(""));
-                lines.push(Line::from(description.as_str()));
-            }
-            
-            if app.should_fetch_details() {
-                lines.push(Line::from(""));
-                lines.push(Line::from(Span::styled(
-                    "Loading detailed information...",
-                    Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
-                )));
-            }
-            
-            lines
-        }
+        package_detail_lines(app, package)
     } else {
         vec![Line::from("No package selected")]
     };
     
     let paragraph = Paragraph::new(content)
         .block(block)
+        .style(Style::default().fg(text_colour(app, "description_unit_text", Color::Reset)))
         .wrap(Wrap { trim: true });
     
     f.render_widget(paragraph, area);
 }
 
-fn draw_installed(f: &mut Frame, app: &App, area: Rect) {
-    let border_style = if app.active_pane == ActivePane::Installed {
-        Style::default().fg(Color::Green)
-    } else {
-        Style::default().fg(Color::Gray)
+// Two pinned packages in side-by-side columns
+fn draw_comparison(f: &mut Frame, app: &App, area: Rect, left: &Package, right: &Package, border_style: Style) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    
+    for (package, column) in [left, right].into_iter().zip(columns.iter()) {
+        let block = Block::default()
+            .title(format!(" Compare: {} ({}) ", package.name, package.source))
+            .title_bottom(" =/Esc: close ")
+            .borders(Borders::ALL)
+            .border_style(border_style);
+        
+        let paragraph = Paragraph::new(package_detail_lines(app, package))
+            .block(block)
+            .wrap(Wrap { trim: true });
+        
+        f.render_widget(paragraph, *column);
+    }
+}
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+fn package_detail_lines<'a>(app: &'a App, package: &'a Package) -> Vec<Line<'a>> {
+    if let Some(details) = app.get_package_details(package) {
+        // Show cached details
+        let mut lines = install_reason_lines(app, package);
+        if app.config.ui.markdown_details {
+            lines.extend(markdown_lines(details));
+        } else {
+            lines.extend(details.lines().map(Line::from));
+        }
+        lines.extend(related_lines(app, package));
+        return lines;
+    }
+    
+    // Show basic info while loading
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Package: ", Style::default().fg(Color::Yellow)),
+            Span::raw(&package.name),
+        ]),
+        Line::from(vec![
+            Span::styled("Source: ", Style::default().fg(Color::Cyan)),
+            Span::raw(&package.source),
+        ]),
+    ];
+    
+    if let Some(repo) = &package.repo {
+        lines.push(Line::from(vec![
+            Span::styled("Repository: ", Style::default().fg(Color::Cyan)),
+            Span::raw(repo),
+        ]));
+    }
+    
+    if let Some(version) = &package.version {
+        lines.push(Line::from(vec![
+            Span::styled("Version: ", Style::default().fg(Color::Magenta)),
+            Span::raw(version),
+        ]));
+    }
+    
+    if let Some(arch) = &package.arch {
+        lines.push(Line::from(vec![
+            Span::styled("Architecture: ", Style::default().fg(Color::Magenta)),
+            Span::raw(arch),
+        ]));
+    }
+    
+    if let Some(url) = &package.url {
+        lines.push(Line::from(vec![
+            Span::styled("URL: ", Style::default().fg(Color::Blue)),
+            Span::raw(url),
+        ]));
+    }
+    
+    lines.extend(install_reason_lines(app, package));
+    
+    if let Some(description) = &package.description {
+        lines.push(Line::from(""));
+        lines.push(Line::from(description.as_str()));
+    }
+    
+    if app.is_fetching_details(package) {
+        // The main loop redraws every tick, which animates the spinner
+        let frame = (app.last_selection_time.elapsed().as_millis() / 100) as usize % SPINNER.len();
+        let what = if package.source == "aur" || package.source == "paru" { "AUR details" } else { "details" };
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("{} Fetching {}…", SPINNER[frame], what),
+            Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+        )));
+    } else if app.should_fetch_details() || app.compare_packages.is_some() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Loading detailed information...",
+            Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+        )));
+    }
+    
+    lines.extend(related_lines(app, package));
+    lines
+}
+
+// Optional dependencies, which `r` steps through and `R` adds to the install selection
+fn related_lines<'a>(app: &'a App, package: &'a Package) -> Vec<Line<'a>> {
+    let mut lines = Vec::new();
+    if package.optional_deps.is_empty() {
+        return lines;
+    }
+    
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Related packages (r: pick, R: select):",
+        Style::default().fg(Color::Yellow),
+    )));
+    
+    let cursor = app.related_cursor_for(package);
+    for (i, dependency) in package.optional_deps.iter().enumerate() {
+        let related = app.related_package(package, dependency);
+        let selected = related.is_some_and(|related| app.is_package_selected(related));
+        let installed = related.is_some_and(|related| related.installed || app.get_installed_copy(related).is_some());
+        
+        let marker = format!(
+            "{} {} {} ",
+            if cursor == Some(i) { "›" } else { " " },
+            if selected { "[x]" } else { "[ ]" },
+            if installed { "✓" } else { " " },
+        );
+        let style = if cursor == Some(i) {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else if related.is_none() {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![Span::raw(marker), Span::styled(dependency.as_str(), style)]));
+    }
+    
+    lines
+}
+
+// "Why is this installed?" - explicit vs. dependency, and what depends on it
+fn install_reason_lines<'a>(app: &'a App, package: &'a Package) -> Vec<Line<'a>> {
+    let mut lines = Vec::new();
+    let Some(installed) = app.get_installed_copy(package) else {
+        return lines;
     };
     
+    if let Some(reason) = installed.install_reason {
+        let reason = match reason {
+            InstallReason::Explicit => "explicitly installed",
+            InstallReason::Dependency => "installed as a dependency",
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Reason: ", Style::default().fg(Color::Green)),
+            Span::raw(reason),
+        ]));
+    }
+    
+    if !installed.required_by.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("Required by: ", Style::default().fg(Color::Green)),
+            Span::raw(installed.required_by.join(", ")),
+        ]));
+    }
+    
+    lines
+}
+
+fn draw_installed(f: &mut Frame, app: &App, area: Rect) {
+    let border_style = unit_border_style(app, "installed_list_unit", app.active_pane == ActivePane::Installed);
+    
     // Calculate visible range
     let visible_height = area.height.saturating_sub(2) as usize;
     let start = app.installed_scroll;
-    let end = (start + visible_height).min(app.installed_packages.len());
-    
-    let scroll_info = if app.installed_packages.len() > visible_height {
-        format!(" ({}-{}/{}) ", 
-            start + 1, 
-            end, 
-            app.installed_packages.len()
-        )
-    } else {
-        format!(" ({}) ", app.installed_packages.len())
-    };
-    let title = format!(" Installed{} ", scroll_info);
+    let end = (start + visible_height).min(app.installed_view.len());
+    
+    let rows = app.installed_view.len();
+    let key = (start, end, rows, visible_height, app.installed_size_total, app.orphans.is_some());
+    let title = app.installed_title.get(key, || {
+        // The total size is of everything installed, so it's left out while listing orphans
+        if app.orphans.is_some() {
+            return format!(" Orphans ({}) ", rows);
+        }
+        let total_size = app.installed_size_total.map(|size| format!(", {}", format_size(size))).unwrap_or_default();
+        if rows > visible_height {
+            format!(" Installed ({}-{}/{}{})  ", start + 1, end, rows, total_size)
+        } else {
+            format!(" Installed ({}{})  ", rows, total_size)
+        }
+    });
     
     let block = Block::default()
-        .title(title)
+        .title(&*title)
         .borders(Borders::ALL)
         .border_style(border_style);
     
-    let items: Vec<ListItem> = app.installed_packages[start..end]
+    let highlight_colour = text_colour(app, "installed_list_unit_highlight_text", Color::White);
+    let text = text_colour(app, "installed_list_unit_text", Color::Green);
+    let items: Vec<ListItem> = app.installed_view[start..end]
         .iter()
+        .map(|&index| &app.installed_packages[index])
         .enumerate()
         .map(|(i, package)| {
             let actual_index = start + i;
@@ -263,36 +513,31 @@ fn draw_installed(f: &mut Frame, app: &App, area: Rect) {
             let content = format!("✓ {:<20} {}", package.name, package.source);
             
             let style = if is_selected {
-                Style::default().bg(Color::DarkGray).fg(Color::White)
+                Style::default().bg(Color::DarkGray).fg(highlight_colour)
             } else {
-                Style::default().fg(Color::Green)
+                Style::default().fg(text)
             };
             
             ListItem::new(content).style(style)
         })
         .collect();
     
-    let list = List::new(items).block(block);
+    let list = List::new(items).block(block).highlight_symbol(&app.config.ui.cursor_symbol);
     
-    let mut list_state = ListState::default();
-    if !app.installed_packages.is_empty() && app.installed_selected < app.installed_packages.len() {
-        if app.installed_selected >= start && app.installed_selected < end {
-            list_state.select(Some(app.installed_selected - start));
-        }
-    }
+    let mut list_state = app.installed_list_state.borrow_mut();
+    *list_state.offset_mut() = 0;
+    list_state.select((start..end).contains(&app.installed_selected).then(|| app.installed_selected - start));
     
-    f.render_stateful_widget(list, area, &mut list_state);
+    f.render_stateful_widget(list, area, &mut *list_state);
 }
 
 fn draw_terminal(f: &mut Frame, app: &App, area: Rect) {
-    let border_style = if app.active_pane == ActivePane::Terminal {
-        Style::default().fg(Color::Green)
-    } else {
-        Style::default().fg(Color::Gray)
-    };
+    let border_style = unit_border_style(app, "terminal_unit", app.active_pane == ActivePane::Terminal);
     
     let selected_count = app.get_selected_count();
-    let title = if selected_count > 0 {
+    let title = if app.showing_preview {
+        " Terminal - dry run ".to_string()
+    } else if selected_count > 0 {
         format!(" Terminal - {} selected ", selected_count)
     } else {
         " Terminal ".to_string()
@@ -303,28 +548,62 @@ fn draw_terminal(f: &mut Frame, app: &App, area: Rect) {
         .borders(Borders::ALL)
         .border_style(border_style);
     
-    let content = if selected_count > 0 {
+    let content = if app.showing_preview && app.preview_output.is_empty() {
+        vec![Line::from(Span::styled("Running the dry run…", Style::default().fg(Color::Yellow)))]
+    } else if selected_count > 0 && !app.showing_preview {
+        let header = match app.selection_download_size() {
+            Some(size) => format!("Selected packages for installation ({} to download):", format_size(size)),
+            None => "Selected packages for installation:".to_string(),
+        };
         let mut lines = vec![
-            Line::from("Selected packages for installation:"),
+            Line::from(header),
             Line::from(""),
         ];
         
-        for (i, package_key) in app.get_selected_packages_list().iter().enumerate() {
-            if i < 10 { // Show max 10 packages to avoid overflow
-                lines.push(Line::from(format!("  {}", package_key)));
-            } else if i == 10 {
-                lines.push(Line::from(format!("  ... and {} more", selected_count - 10)));
-                break;
+        // Keep the review cursor in view: header (2) + footer (2) + borders (2)
+        let visible_height = (area.height.saturating_sub(6) as usize).max(1);
+        let cursor = app.selection_cursor.min(selected_count - 1);
+        let start = (cursor + 1).saturating_sub(visible_height);
+        let is_active = app.active_pane == ActivePane::Terminal;
+        let conflicts = app.selection_conflicts();
+        
+        for (i, package_key) in app.get_selected_packages_list().iter().enumerate().skip(start).take(visible_height) {
+            let conflict = conflicts.get(package_key);
+            let style = if is_active && i == cursor {
+                Style::default().bg(Color::DarkGray).fg(text_colour(app, "terminal_unit_highlight_text", Color::White))
+            } else if conflict.is_some() {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            let mut text = format!("  {}", package_key);
+            if let Some(version) = app.pinned_versions.get(package_key) {
+                text.push_str(&format!(" = {}", version));
+            }
+            if let Some(installed) = conflict {
+                text.push_str(&format!(" (conflicts with {})", installed.join(", ")));
             }
+            lines.push(Line::from(Span::styled(text, style)));
         }
         
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
-            "Press Enter to install selected packages",
+            match (app.read_only, is_active) {
+                (true, _) => "Read-only mode: y copies the install commands",
+                (false, true) => "Enter: install  d/Space: remove  j/k: move",
+                (false, false) => "Press Enter to install selected packages",
+            },
             Style::default().fg(Color::Yellow)
         )));
         
         lines
+    } else if !app.terminal_lines().is_empty() {
+        // Most recent command output, newest at the bottom unless scrolled back
+        let output = app.terminal_lines();
+        let visible_height = area.height.saturating_sub(2) as usize;
+        let end = output.len().saturating_sub(app.terminal_scroll);
+        let start = end.saturating_sub(visible_height);
+        output[start..end].iter().map(|line| Line::from(line.as_str())).collect()
     } else {
         vec![
             Line::from("No packages selected for installation."),
@@ -336,7 +615,413 @@ fn draw_terminal(f: &mut Frame, app: &App, area: Rect) {
     
     let paragraph = Paragraph::new(content)
         .block(block)
+        .style(Style::default().fg(text_colour(app, "terminal_unit_text", Color::Reset)))
         .wrap(Wrap { trim: true });
     
     f.render_widget(paragraph, area);
-}
\ No newline at end of file
+}
+
+pub fn centered_rect(size: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(size.width);
+    let height = height.min(size.height);
+    Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// Asks before running commands that need root, since sudo will prompt for a password
+fn draw_install_confirmation(f: &mut Frame, app: &App) {
+    let mut lines = Vec::new();
+    
+    if app.confirming_selection {
+        for (package_key, installed) in app.selection_conflicts().iter() {
+            lines.push(Line::from(Span::styled(
+                format!("{} conflicts with installed {}", package_key, installed.join(", ")),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            )));
+        }
+        if !lines.is_empty() {
+            lines.push(Line::from("The package manager may ask to remove them, or fail partway through."));
+            lines.push(Line::from(""));
+        }
+        
+        let out_of_date = app.selection_out_of_date();
+        for (package_key, days) in &out_of_date {
+            let flagged = match days {
+                0 => "today".to_string(),
+                1 => "1 day ago".to_string(),
+                days => format!("{} days ago", days),
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{} was flagged out-of-date {}", package_key, flagged),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            )));
+        }
+        if !out_of_date.is_empty() {
+            lines.push(Line::from("Its PKGBUILD may no longer build, or build an old release."));
+            lines.push(Line::from(""));
+        }
+    }
+    
+    let needs_root = app.confirm_commands.iter().any(|command| app.needs_root(command));
+    lines.push(Line::from(Span::styled(
+        if needs_root { "These commands need root privileges:" } else { "These commands will run:" },
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    )));
+    lines.push(Line::from(""));
+    
+    for command in &app.confirm_commands {
+        lines.push(Line::from(format!("  {}", command)));
+    }
+    
+    lines.push(Line::from(""));
+    let show_dry_run = app.confirming_selection && !app.preview_commands().is_empty();
+    if show_dry_run {
+        lines.push(Line::from(Span::styled("Dry run:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+    }
+    let dry_run_at = lines.len();
+    if show_dry_run {
+        lines.push(Line::from(""));
+    }
+    if needs_root {
+        if std::io::stdin().is_terminal() {
+            lines.push(Line::from(format!("pmux will step aside so {} can ask for your password.", app.sudoers)));
+        } else {
+            lines.push(Line::from(Span::styled(
+                format!("stdin is not a terminal, so {} cannot ask for a password. Run pmux from an interactive terminal or copy the commands with 'y'.", app.sudoers),
+                Style::default().fg(Color::Red)
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "Enter/y: run  Esc/n: cancel",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+    )));
+    
+    // The dry run gets whatever height the rest leaves, one screen row per output line
+    if show_dry_run {
+        let room = (f.size().height as usize).saturating_sub(lines.len() + 4).max(1);
+        let mut dry_run: Vec<Line> = if app.preview_output.is_empty() {
+            let status = if app.previews_running > 0 { "  running…" } else { "  nothing printed" };
+            vec![Line::from(Span::styled(status, Style::default().fg(Color::Gray)))]
+        } else {
+            app.preview_output.iter().map(|line| Line::from(format!("  {}", line.chars().take(66).collect::<String>()))).collect()
+        };
+        if dry_run.len() > room {
+            let hidden = dry_run.len() - room + 1;
+            dry_run.truncate(room - 1);
+            dry_run.push(Line::from(Span::styled(
+                format!("  … {} more lines, p shows them all after cancelling", hidden),
+                Style::default().fg(Color::Gray)
+            )));
+        }
+        lines.splice(dry_run_at..dry_run_at, dry_run);
+    }
+    
+    let area = centered_rect(f.size(), 72, lines.len() as u16 + 4);
+    let title = if app.is_undoing() { " Confirm removal " } else { " Confirm installation " };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn draw_install_summary(f: &mut Frame, app: &App) {
+    let succeeded = app.install_summary.iter().filter(|result| result.succeeded()).count();
+    let failed = app.install_summary.len() - succeeded;
+    
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(format!("{} succeeded", succeeded), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::raw("  "),
+            Span::styled(
+                format!("{} failed", failed),
+                if failed > 0 {
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Gray)
+                }
+            ),
+        ]),
+        Line::from(""),
+    ];
+    
+    for result in &app.install_summary {
+        let (mark, style) = if result.succeeded() {
+            ("✓", Style::default().fg(Color::Green))
+        } else {
+            ("✗", Style::default().fg(Color::Red))
+        };
+        let outcome = match result.status {
+            Some(_) if result.interrupted() => " (aborted)".to_string(),
+            Some(status) if !status.success() => format!(" ({})", status),
+            None => " (failed to start)".to_string(),
+            _ => String::new(),
+        };
+        lines.push(Line::from(Span::styled(format!("{} {}{}", mark, result.command, outcome), style)));
+    }
+    
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Enter/c: continue  q: quit",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+    )));
+    
+    let area = centered_rect(f.size(), 72, lines.len() as u16 + 4);
+    let block = Block::default()
+        .title(" Installation summary ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if failed > 0 { Color::Red } else { Color::Green }));
+    
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn draw_managers_overview(f: &mut Frame, app: &App, managers: &[ManagerStatus]) {
+    let header = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("      {:<12} {:<14} {:<14} {:<12} {}", "Manager", "Stratum", "Status", "enabled_pm", "Executable"),
+        header,
+    ))];
+    
+    for (i, manager) in managers.iter().enumerate() {
+        let shown = match (manager.detected, app.disabled_managers.contains(&manager.name)) {
+            (false, _) => "     ",
+            (true, false) => " [x] ",
+            (true, true) => " [ ] ",
+        };
+        let (status, status_colour) = if manager.detected {
+            ("detected", Color::Green)
+        } else {
+            ("not detected", Color::Red)
+        };
+        let executable = match &manager.executable {
+            Some((executable, true)) => format!("{} (found)", executable),
+            Some((executable, false)) => format!("{} (not on PATH)", executable),
+            None => "no definition".to_string(),
+        };
+        let cursor = if i == app.managers_cursor { "›" } else { " " };
+        lines.push(Line::from(vec![
+            Span::raw(format!("{}{}", cursor, shown)),
+            Span::raw(format!("{:<12} ", manager.name)),
+            Span::raw(format!("{:<14} ", manager.stratum.as_deref().unwrap_or("-"))),
+            Span::styled(format!("{:<14} ", status), Style::default().fg(status_colour)),
+            Span::raw(format!("{:<12} ", if manager.enabled { "listed" } else { "not listed" })),
+            Span::raw(executable),
+        ]));
+    }
+    
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Space: show/hide packages  s: save to config  Enter/Esc/m: close",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+    )));
+    
+    let area = centered_rect(f.size(), 96, lines.len() as u16 + 2);
+    let block = Block::default()
+        .title(" Package managers ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+// The loaded definitions and the files that failed to load, with the highlighted one's
+// commands or error underneath
+fn draw_configs_overview(f: &mut Frame, app: &App) {
+    let configs = app.loaded_configs();
+    let load_errors = &app.pm_registry.load_errors;
+    let cursor = |row: usize| if row == app.configs_cursor { "›" } else { " " };
+    let failed = Style::default().fg(Color::Red);
+    
+    let mut lines = Vec::new();
+    if configs.is_empty() && load_errors.is_empty() {
+        lines.push(Line::from(Span::styled(" No definitions in pkgmanagers/", failed)));
+    }
+    for (i, config) in configs.iter().enumerate() {
+        let (executable, colour) = if app.pm_registry.is_available(config) {
+            (format!("{} (found)", config.executable), Color::Green)
+        } else {
+            (format!("{} (not on PATH)", config.executable), Color::Gray)
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("{} {:<12} {:<20} ", cursor(i), config.name, config.display_name)),
+            Span::styled(executable, Style::default().fg(colour)),
+        ]));
+    }
+    for (i, (file, _)) in load_errors.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::raw(format!("{} {:<12} ", cursor(configs.len() + i), file)),
+            Span::styled("failed to load", failed),
+        ]));
+    }
+    
+    lines.push(Line::from(""));
+    if let Some(config) = configs.get(app.configs_cursor) {
+        let label = Style::default().fg(Color::Yellow);
+        let commands = [
+            ("list", Some(&config.list_packages_cmd)),
+            ("installed", Some(&config.list_installed_cmd)),
+            ("search", Some(&config.search_cmd)),
+            ("install", Some(&config.install_cmd)),
+            ("preview", config.preview_cmd.as_ref()),
+            ("remove", config.remove_cmd.as_ref()),
+            ("downgrade", config.downgrade_cmd.as_ref()),
+            ("orphans", config.orphans_cmd.as_ref()),
+        ];
+        for (name, command) in commands {
+            if let Some(command) = command {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {:<10} ", name), label),
+                    Span::raw(command.as_str()),
+                ]));
+            }
+        }
+        if config.requires_root {
+            lines.push(Line::from(Span::styled("  runs install and remove as root", Style::default().fg(Color::Gray))));
+        }
+    } else if let Some((_, error)) = app.configs_cursor.checked_sub(configs.len()).and_then(|i| load_errors.get(i)) {
+        for line in error.lines() {
+            lines.push(Line::from(Span::styled(format!("  {}", line), failed)));
+        }
+    }
+    
+    if let Ok(dir) = Config::get_config_dir() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Definitions are read from {}", dir.join("pkgmanagers").display())));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "↑/↓: move  r: reload after editing  Enter/Esc/P: close",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+    )));
+    
+    let area = centered_rect(f.size(), 100, lines.len() as u16 + 2);
+    let block = Block::default()
+        .title(" Manager definitions ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_downgrade_picker(f: &mut Frame, picker: &DowngradePicker) {
+    let mut lines = vec![Line::from(format!(
+        "Installed: {}",
+        picker.installed_version.as_deref().unwrap_or("unknown version")
+    ))];
+    lines.push(Line::from(""));
+    
+    for (i, option) in picker.options.iter().enumerate() {
+        let style = if i == picker.cursor {
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let cursor = if i == picker.cursor { "› " } else { "  " };
+        lines.push(Line::from(Span::styled(format!("{}{}", cursor, option.label), style)));
+    }
+    
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "↑/↓: pick a version  Enter: downgrade  Esc: cancel",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+    )));
+    
+    let area = centered_rect(f.size(), 64, lines.len() as u16 + 2);
+    let block = Block::default()
+        .title(format!(" Downgrade {} ", picker.package))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+// Shown instead of the main layout when no package manager could be detected
+pub fn draw_no_managers(f: &mut Frame) {
+    let size = f.size();
+    
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "No supported package managers were found",
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        )),
+        Line::from(""),
+        Line::from("pmux looks for:"),
+    ];
+    
+    for (name, path) in DETECTION_HINTS {
+        lines.push(Line::from(format!("  {:<8} {}", name, path)));
+    }
+    lines.push(Line::from(format!("  {:<8} {}", "bedrock", "/bedrock/strata/*")));
+    
+    lines.push(Line::from(""));
+    let definitions_dir = Config::get_config_dir()
+        .map(|dir| format!("{}/", dir.join("pkgmanagers").display()))
+        .unwrap_or_else(|_| "the pkgmanagers/ config directory".to_string());
+    lines.push(Line::from("To use another package manager, add a .toml definition to"));
+    lines.push(Line::from(format!("  {}", definitions_dir)));
+    lines.push(Line::from("(see the README there)."));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Press any key to exit",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+    )));
+    
+    // Center the message box, widened when the config path is long
+    let width = lines.iter().map(|line| line.width() as u16 + 4).max().unwrap_or(0).max(64);
+    let area = centered_rect(size, width, lines.len() as u16 + 2);
+    
+    let block = Block::default()
+        .title(" pmux ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    
+    f.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn theme_colours_reach_the_pane_borders() {
+        let mut app = App::default();
+        app.active_pane = ActivePane::Results;
+        app.config.main.theme = Some("dracula".to_string());
+        app.config.apply_theme();
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal.draw(|f| draw_installed(f, &app, f.size())).unwrap();
+        // Unfocused: the theme's border colour
+        assert_eq!(terminal.backend().buffer().get(0, 0).fg, Color::Rgb(0xbd, 0x93, 0xf9));
+
+        app.active_pane = ActivePane::Installed;
+        terminal.draw(|f| draw_installed(f, &app, f.size())).unwrap();
+        // Focused: the theme's highlight colour
+        assert_eq!(terminal.backend().buffer().get(0, 0).fg, Color::Rgb(0x50, 0xfa, 0x7b));
+    }
+}
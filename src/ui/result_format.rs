@@ -0,0 +1,106 @@
+use crate::core::config::DEFAULT_RESULT_FORMAT;
+use crate::core::package_managers::Package;
+
+// A `[layout] result_format` template, parsed once at startup.
+// Placeholders: {selected} {name} {installed} {source} {version} {arch}, each optionally
+// padded to a width with `{name:38}`. Anything else is copied through as-is.
+#[derive(Debug, Clone)]
+pub struct ResultFormat {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Field(Field, usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Selected,
+    Name,
+    Installed,
+    Source,
+    Version,
+    Arch,
+}
+
+impl ResultFormat {
+    pub fn parse(template: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut rest = template;
+
+        while let Some(open) = rest.find('{') {
+            let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+                break;
+            };
+
+            let (name, width) = match rest[open + 1..close].split_once(':') {
+                Some((name, width)) => (name, width.trim().parse().ok()),
+                None => (&rest[open + 1..close], Some(0)),
+            };
+            let field = match name.trim() {
+                "selected" => Some(Field::Selected),
+                "name" => Some(Field::Name),
+                "installed" => Some(Field::Installed),
+                "source" => Some(Field::Source),
+                "version" => Some(Field::Version),
+                "arch" => Some(Field::Arch),
+                _ => None,
+            };
+
+            match (field, width) {
+                (Some(field), Some(width)) => {
+                    if open > 0 {
+                        segments.push(Segment::Literal(rest[..open].to_string()));
+                    }
+                    segments.push(Segment::Field(field, width));
+                }
+                // Not a placeholder we know, keep the braces literally
+                _ => segments.push(Segment::Literal(rest[..=close].to_string())),
+            }
+            rest = &rest[close + 1..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        ResultFormat { segments }
+    }
+
+    // Templates without {version} get it as a right-aligned column instead
+    pub fn has_version(&self) -> bool {
+        self.segments.iter().any(|segment| matches!(segment, Segment::Field(Field::Version, _)))
+    }
+
+    pub fn render(&self, package: &Package, selected: bool) -> String {
+        let mut row = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => row.push_str(text),
+                Segment::Field(field, width) => {
+                    let value = match field {
+                        Field::Selected => (if selected { "●" } else { " " }).to_string(),
+                        Field::Name if package.is_group => format!("{} [group]", package.name),
+                        Field::Name => package.name.clone(),
+                        Field::Installed => (if package.installed { "✓" } else { " " }).to_string(),
+                        Field::Source => package.source.clone(),
+                        Field::Version => package.version.clone().unwrap_or_default(),
+                        Field::Arch => package.arch.clone().unwrap_or_default(),
+                    };
+                    row.push_str(&format!("{:<width$}", value, width = *width));
+                }
+            }
+        }
+
+        row
+    }
+}
+
+impl Default for ResultFormat {
+    fn default() -> Self {
+        Self::parse(DEFAULT_RESULT_FORMAT)
+    }
+}
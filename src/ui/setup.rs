@@ -0,0 +1,165 @@
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{
+    backend::Backend,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::core::config::{Config, THEMES};
+use crate::core::local::LocalPackageManager;
+use crate::core::package_managers::binary_in_path;
+use crate::ui::render::centered_rect;
+
+// Programs offered for running installs as root, in order of preference
+const SUDOERS: &[&str] = &["sudo", "doas", "run0"];
+
+// First-run choices: which detected managers to show (unticked ones go in disabled_pm), a theme
+// and the program used for root. Rows are one per manager, then the theme, then the sudoers program
+struct Setup {
+    managers: Vec<(String, bool)>,
+    theme: usize, // 0 is no theme, then THEMES in order
+    sudoers: Vec<String>,
+    sudoer: usize,
+    cursor: usize,
+}
+
+impl Setup {
+    fn new(detected: &[LocalPackageManager]) -> Self {
+        // One row per manager, however many Bedrock strata it was found in
+        let mut managers: Vec<(String, bool)> = Vec::new();
+        for manager in detected {
+            if !managers.iter().any(|(name, _)| name == &manager.name) {
+                managers.push((manager.name.clone(), true));
+            }
+        }
+
+        // Only offer what's installed, falling back to the default when none of them are
+        let mut sudoers: Vec<String> = SUDOERS.iter().filter(|s| binary_in_path(s)).map(|s| s.to_string()).collect();
+        if sudoers.is_empty() {
+            sudoers.push(Config::default().main.sudoers);
+        }
+
+        Setup {
+            managers,
+            theme: 0,
+            sudoers,
+            sudoer: 0,
+            cursor: 0,
+        }
+    }
+
+    fn theme_row(&self) -> usize {
+        self.managers.len()
+    }
+
+    fn sudoers_row(&self) -> usize {
+        self.managers.len() + 1
+    }
+
+    fn theme_name(&self) -> &'static str {
+        match self.theme.checked_sub(1) {
+            Some(i) => THEMES[i].0,
+            None => "none",
+        }
+    }
+
+    // Toggle the manager under the cursor, or step the theme or sudoers choice
+    fn change(&mut self, forward: bool) {
+        if self.cursor == self.theme_row() {
+            let count = THEMES.len() + 1;
+            self.theme = if forward { (self.theme + 1) % count } else { (self.theme + count - 1) % count };
+        } else if self.cursor == self.sudoers_row() {
+            let count = self.sudoers.len();
+            self.sudoer = if forward { (self.sudoer + 1) % count } else { (self.sudoer + count - 1) % count };
+        } else if let Some((_, enabled)) = self.managers.get_mut(self.cursor) {
+            *enabled = !*enabled;
+        }
+    }
+
+    // None while the setup is still open; Some(true) saves the choices, Some(false) the defaults
+    fn handle_key(&mut self, code: KeyCode) -> Option<bool> {
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => self.cursor = (self.cursor + 1).min(self.sudoers_row()),
+            KeyCode::Char(' ') | KeyCode::Right | KeyCode::Char('l') => self.change(true),
+            KeyCode::Left | KeyCode::Char('h') => self.change(false),
+            KeyCode::Enter => return Some(true),
+            KeyCode::Esc | KeyCode::Char('q') => return Some(false),
+            _ => {}
+        }
+        None
+    }
+
+    fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.pm.enabled_pm = self.managers.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name.clone()).collect();
+        config.pm.disabled_pm = self.managers.iter().filter(|(_, enabled)| !*enabled).map(|(name, _)| name.clone()).collect();
+        config.main.theme = self.theme.checked_sub(1).map(|i| THEMES[i].0.to_string());
+        config.main.sudoers = self.sudoers[self.sudoer].clone();
+        config
+    }
+}
+
+// Shown on first launch, when there's no config.toml yet. Whatever is chosen (or the defaults,
+// if the setup is skipped) is written to config.toml so this only happens once
+pub fn run_setup<B: Backend>(terminal: &mut Terminal<B>, detected: &[LocalPackageManager]) -> Result<Config, Box<dyn std::error::Error>> {
+    let mut setup = Setup::new(detected);
+    let confirmed = loop {
+        terminal.draw(|f| draw_setup(f, &setup))?;
+        if let Event::Key(key) = event::read()? {
+            if let Some(confirmed) = setup.handle_key(key.code) {
+                break confirmed;
+            }
+        }
+    };
+
+    let mut config = if confirmed { setup.config() } else { Config::default() };
+    if let Err(e) = config.save() {
+        log::warn!("Failed to write the first-run config: {}", e);
+    }
+    config.apply_theme();
+    Ok(config)
+}
+
+fn draw_setup(f: &mut Frame, setup: &Setup) {
+    let header = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let cursor = |row: usize| if row == setup.cursor { "›" } else { " " };
+
+    let mut lines = vec![
+        Line::from(Span::styled("Welcome to pmux", header)),
+        Line::from(""),
+        Line::from("Package managers found on this system:"),
+    ];
+    if setup.managers.is_empty() {
+        lines.push(Line::from(Span::styled("   none detected", Style::default().fg(Color::Red))));
+    }
+    for (i, (name, enabled)) in setup.managers.iter().enumerate() {
+        let checkbox = if *enabled { "[x]" } else { "[ ]" };
+        lines.push(Line::from(format!("{}  {} {}", cursor(i), checkbox, name)));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!("{}  Theme:        ‹ {} ›", cursor(setup.theme_row()), setup.theme_name())));
+    lines.push(Line::from(format!(
+        "{}  Run root as:  ‹ {} ›",
+        cursor(setup.sudoers_row()),
+        setup.sudoers[setup.sudoer]
+    )));
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "↑/↓: move  Space/←/→: change  Enter: save  Esc: use defaults",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC)
+    )));
+
+    let area = centered_rect(f.size(), 64, lines.len() as u16 + 2);
+
+    let block = Block::default()
+        .title(" First-run setup ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+
+    f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}